@@ -0,0 +1,17 @@
+use std::{env, path::PathBuf, process::Command};
+
+fn main() {
+    let testlib = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap()).join("../decan-testlib/src/lib.rs");
+    println!("cargo:rerun-if-changed={}", testlib.display());
+
+    let status = Command::new(env::var_os("RUSTC").unwrap())
+        .arg(&testlib)
+        .args(["--crate-name", "decan_testlib", "--crate-type", "cdylib", "--edition", "2021"])
+        .arg("--target")
+        .arg(env::var_os("TARGET").unwrap())
+        .arg("--out-dir")
+        .arg(env::var_os("OUT_DIR").unwrap())
+        .status()
+        .unwrap();
+    assert!(status.success(), "Failed to compile decan-testlib!");
+}
@@ -0,0 +1,3 @@
+//! Tests for embedding libraries with `decan::include_library!`.
+//!
+//! The build script compiles decan-testlib, so that the tests can embed it at compile time.
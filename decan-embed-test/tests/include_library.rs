@@ -0,0 +1,18 @@
+#![cfg(target_os = "linux")]
+
+use decan::{can::Can, include_library, LazyLibrary, SymbolGroup};
+
+#[derive(SymbolGroup)]
+pub struct DecanTestlib {
+    pub square_int: extern "C" fn(i32) -> i32,
+}
+
+static TESTLIB: LazyLibrary = include_library!(concat!(env!("OUT_DIR"), "/libdecan_testlib.so"));
+
+#[test]
+fn test_include_library() {
+    assert!(!TESTLIB.is_loaded());
+    let can = unsafe { Can::<_, DecanTestlib>::with_handle(TESTLIB.get().unwrap()).unwrap() };
+    assert_eq!(can.symbols().square_int.call(7), 49);
+    assert!(TESTLIB.is_loaded());
+}
@@ -1,8 +1,7 @@
-use std::{ffi::CString, iter, sync::LazyLock};
+use std::{ffi::CString, iter};
 
-use proc_macro::Ident;
 use proc_macro2::{Literal, TokenStream};
-use quote::{quote, quote_spanned};
+use quote::{format_ident, quote, quote_spanned};
 use syn::spanned::Spanned;
 
 pub(crate) fn generate(input: syn::DeriveInput) -> syn::Result<TokenStream> {
@@ -26,13 +25,13 @@ pub(crate) fn generate(input: syn::DeriveInput) -> syn::Result<TokenStream> {
         }
         syn::Data::Enum(data_enum) => {
             return Err(syn::Error::new_spanned(
-                &data_enum.enum_token,
+                data_enum.enum_token,
                 "derive(SymbolGroup) cannot be implemented for enums",
             ))
         }
         syn::Data::Union(data_union) => {
             return Err(syn::Error::new_spanned(
-                &data_union.union_token,
+                data_union.union_token,
                 "derive(SymbolGroup) cannot be implemented for unions",
             ))
         }
@@ -42,34 +41,290 @@ pub(crate) fn generate(input: syn::DeriveInput) -> syn::Result<TokenStream> {
         fields.iter().map(extract_field_info).collect();
     let fields_info = fields_info?;
 
-    let fields_gen: Vec<TokenStream> = iter::zip(fields.iter(), fields_info.iter()).map(generate_field).collect();
+    let container_info = extract_container_info(&input.attrs)?;
+
+    let load_gen = generate_load(fields, &fields_info, container_info.strict);
+    let load_strict_gen = generate_load(fields, &fields_info, true);
+
+    let vis = &input.vis;
+    let branded_ident = format_ident!("{}Branded", ident);
+    let accessors_gen = if container_info.accessors {
+        let accessors: Vec<TokenStream> = iter::zip(fields.iter(), fields_info.iter())
+            .map(|field| generate_accessor(vis, field))
+            .collect();
+        quote! {
+            impl<'lib> #branded_ident<'lib> {
+                #(#accessors)*
+            }
+        }
+    } else {
+        TokenStream::new()
+    };
+
+    let branded_doc = format!(
+        " The [branded][::decan::SymbolGroup::Branded] form of [`{ident}`], whose symbols cannot outlive their library."
+    );
+    let branded_fields: Vec<TokenStream> =
+        iter::zip(fields.iter(), fields_info.iter()).map(generate_branded_field).collect();
+    let brands_gen: Vec<TokenStream> = iter::zip(fields.iter(), fields_info.iter()).map(generate_brand).collect();
+
+    let probes_gen: Vec<TokenStream> = iter::zip(fields.iter(), fields_info.iter()).map(generate_probe).collect();
+    let symbols_gen: Vec<TokenStream> = iter::zip(fields.iter(), fields_info.iter()).map(generate_symbol_info).collect();
 
     Ok(quote! {
         #[automatically_derived]
         unsafe impl ::decan::SymbolGroup for #ident {
-            unsafe fn load(handle: ::decan::raw::Handle) -> Result<Self, ::decan::SymbolGroupError> {
-                Ok(Self {
-                    #(#fields_gen),*
-                })
+            unsafe fn load(handle: ::decan::raw::Handle) -> Result<Self, ::decan::SymbolGroupErrors> {
+                #load_gen
+            }
+
+            unsafe fn load_strict(handle: ::decan::raw::Handle) -> Result<Self, ::decan::SymbolGroupErrors> {
+                #load_strict_gen
+            }
+
+            unsafe fn probe(handle: ::decan::raw::Handle) -> ::decan::ProbeReport {
+                let mut report = ::decan::ProbeReport::new();
+                #(#probes_gen)*
+                report
+            }
+
+            fn symbols() -> Vec<::decan::SymbolInfo> {
+                let mut symbols = Vec::new();
+                #(#symbols_gen)*
+                symbols
+            }
+
+            type Branded<'lib> = #branded_ident<'lib>;
+
+            unsafe fn brand<'lib>(&self) -> Self::Branded<'lib> {
+                #branded_ident {
+                    #(#brands_gen)*
+                    __lib: ::std::marker::PhantomData,
+                }
             }
         }
+
+        #[doc = #branded_doc]
+        #[derive(Clone, Copy)]
+        #vis struct #branded_ident<'lib> {
+            #(#branded_fields)*
+            __lib: ::std::marker::PhantomData<&'lib ()>,
+        }
+
+        #accessors_gen
     })
 }
 
-fn generate_field((field, field_info): (&syn::Field, &FieldInfo)) -> TokenStream {
+fn generate_accessor(vis: &syn::Visibility, (field, field_info): (&syn::Field, &FieldInfo)) -> TokenStream {
+    let ident = field.ident.as_ref().unwrap();
+    let ty = &field.ty;
+    let span = field.span();
+    match field_info {
+        FieldInfo::Symbol { str_token, .. } => {
+            let doc = format!(" Returns the symbol `{}`.", str_token.to_string().trim_matches('"'));
+            quote_spanned! { span=>
+                #[doc = #doc]
+                #vis fn #ident(&self) -> <#ty as ::decan::Symbol>::Branded<'lib> {
+                    self.#ident
+                }
+            }
+        },
+        FieldInfo::Subgroup => {
+            quote_spanned! { span=>
+                #[doc = " Returns this subgroup."]
+                #vis fn #ident(&self) -> <#ty as ::decan::SymbolGroup>::Branded<'lib> {
+                    self.#ident
+                }
+            }
+        },
+    }
+}
+
+fn generate_branded_field((field, field_info): (&syn::Field, &FieldInfo)) -> TokenStream {
+    let ident = field.ident.as_ref().unwrap();
+    let vis = &field.vis;
+    let ty = &field.ty;
+    let span = field.span();
+    match field_info {
+        FieldInfo::Symbol { .. } => quote_spanned! { span=>
+            #vis #ident: <#ty as ::decan::Symbol>::Branded<'lib>,
+        },
+        FieldInfo::Subgroup => quote_spanned! { span=>
+            #vis #ident: <#ty as ::decan::SymbolGroup>::Branded<'lib>,
+        },
+    }
+}
+
+fn generate_brand((field, field_info): (&syn::Field, &FieldInfo)) -> TokenStream {
+    let ident = field.ident.as_ref().unwrap();
+    let span = field.span();
+    match field_info {
+        FieldInfo::Symbol { .. } => quote_spanned! { span=>
+            #ident: ::decan::Symbol::brand(&self.#ident),
+        },
+        FieldInfo::Subgroup => quote_spanned! { span=>
+            #ident: ::decan::SymbolGroup::brand(&self.#ident),
+        },
+    }
+}
+
+/// Generates the body of `SymbolGroup::load` or `SymbolGroup::load_strict`.
+fn generate_load(
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    fields_info: &[FieldInfo],
+    strict: bool,
+) -> TokenStream {
+    let fields_gen = iter::zip(fields.iter(), fields_info.iter()).map(|field| generate_field(field, strict));
+    let field_idents = fields.iter().map(|field| field.ident.as_ref().unwrap());
+    let field_locals = fields.iter().map(field_local);
+    quote! {
+        let mut errors = ::decan::SymbolGroupErrors::new();
+        #(#fields_gen)*
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(Self {
+            #(#field_idents: #field_locals.unwrap()),*
+        })
+    }
+}
+
+#[derive(Default)]
+struct ContainerInfo {
+    accessors: bool,
+    strict: bool,
+}
+
+fn extract_container_info(attrs: &[syn::Attribute]) -> syn::Result<ContainerInfo> {
+    let mut info = ContainerInfo::default();
+    for attr in attrs {
+        if attr.path().is_ident("symbol_group") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("accessors") {
+                    info.accessors = true;
+                    Ok(())
+                } else if meta.path.is_ident("strict") {
+                    info.strict = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported #[symbol_group] option"))
+                }
+            })?;
+        }
+    }
+    Ok(info)
+}
+
+fn field_local(field: &syn::Field) -> syn::Ident {
+    format_ident!("field_{}", field.ident.as_ref().unwrap())
+}
+
+fn generate_field((field, field_info): (&syn::Field, &FieldInfo), strict: bool) -> TokenStream {
     let ident = field.ident.as_ref().unwrap();
+    let local = field_local(field);
+    let path = ident.to_string();
+    let ty = &field.ty;
+    let span = field.span();
+    let (load_from, load_versioned_from, load) = if strict {
+        (
+            format_ident!("load_strict_from"),
+            format_ident!("load_versioned_strict_from"),
+            format_ident!("load_strict"),
+        )
+    } else {
+        (
+            format_ident!("load_from"),
+            format_ident!("load_versioned_from"),
+            format_ident!("load"),
+        )
+    };
+    match field_info {
+        FieldInfo::Symbol { str_token, cstr_token, version: None, .. } => {
+            quote_spanned! { span=>
+                let #local = match <#ty as ::decan::Symbol>::#load_from(handle, #cstr_token) {
+                    Ok(value) => Some(value),
+                    Err(err) => {
+                        errors.push(err.in_field(#path, #str_token));
+                        None
+                    }
+                };
+            }
+        },
+        FieldInfo::Symbol { str_token, cstr_token, version: Some(cversion_token), .. } => {
+            // symbol versions only exist on glibc, so the loader for them does too
+            quote_spanned! { span=>
+                #[cfg(all(target_os = "linux", target_env = "gnu"))]
+                let #local = match <#ty as ::decan::Symbol>::#load_versioned_from(handle, #cstr_token, #cversion_token) {
+                    Ok(value) => Some(value),
+                    Err(err) => {
+                        errors.push(err.in_field(#path, #str_token));
+                        None
+                    }
+                };
+                #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+                let #local: ::std::option::Option<#ty> =
+                    ::std::compile_error!("symbol versions are only supported on glibc");
+            }
+        },
+        FieldInfo::Subgroup => {
+            quote_spanned! { span=>
+                let #local = match <#ty as ::decan::SymbolGroup>::#load(handle) {
+                    Ok(value) => Some(value),
+                    Err(errs) => {
+                        errors.extend_nested(#path, errs);
+                        None
+                    }
+                };
+            }
+        },
+    }
+}
+
+fn generate_probe((field, field_info): (&syn::Field, &FieldInfo)) -> TokenStream {
+    let path = field.ident.as_ref().unwrap().to_string();
+    let ty = &field.ty;
+    let span = field.span();
+    match field_info {
+        FieldInfo::Symbol { str_token, cstr_token, version: None, .. } => {
+            quote_spanned! { span=>
+                report.push(::decan::ProbeEntry::probe(handle, #path, #str_token, #cstr_token));
+            }
+        },
+        FieldInfo::Symbol { str_token, cstr_token, version: Some(cversion_token), .. } => {
+            quote_spanned! { span=>
+                #[cfg(all(target_os = "linux", target_env = "gnu"))]
+                report.push(::decan::ProbeEntry::probe_versioned(handle, #path, #str_token, #cstr_token, #cversion_token));
+            }
+        },
+        FieldInfo::Subgroup => {
+            quote_spanned! { span=>
+                report.extend_nested(#path, <#ty as ::decan::SymbolGroup>::probe(handle));
+            }
+        },
+    }
+}
+
+fn generate_symbol_info((field, field_info): (&syn::Field, &FieldInfo)) -> TokenStream {
+    let path = field.ident.as_ref().unwrap().to_string();
     let ty = &field.ty;
     let span = field.span();
     match field_info {
-        FieldInfo::Symbol { str_token, cstr_token } => {
+        FieldInfo::Symbol { name_token, version_token, .. } => {
+            let version = match version_token {
+                Some(version) => quote! { Some(#version) },
+                None => quote! { None },
+            };
             quote_spanned! { span=>
-                #ident: <#ty as ::decan::Symbol>::load_from(handle, #cstr_token)
-                    .map_err(|err| err.in_group(#str_token))?
+                symbols.push(::decan::SymbolInfo::new::<#ty, _>(#path, #name_token, #version));
             }
         },
         FieldInfo::Subgroup => {
             quote_spanned! { span=>
-                #ident: <#ty as ::decan::SymbolGroup>::load(handle)?
+                symbols.extend(
+                    <#ty as ::decan::SymbolGroup>::symbols()
+                        .into_iter()
+                        .map(|info| info.nested(#path))
+                );
             }
         },
     }
@@ -77,12 +332,101 @@ fn generate_field((field, field_info): (&syn::Field, &FieldInfo)) -> TokenStream
 
 enum FieldInfo {
     Symbol {
+        /// The symbol's display name, including its version.
         str_token: Literal,
         cstr_token: Literal,
+        version: Option<Literal>,
+        /// The symbol's name and version as plain string literals.
+        name_token: Literal,
+        version_token: Option<Literal>,
     },
     Subgroup,
 }
 
+const SYMBOL_FORMAT_HINT: &str = "\
+    #[symbol] expects one of the formats: #[symbol = \"symbol_name\"] or \
+    #[symbol(name = \"symbol_name\", version = \"symbol_version\")], where \"symbol_name\" \
+    and \"symbol_version\" are valid C strings";
+
+fn lit_str_to_cstr(lit_str: &syn::LitStr, what: &str) -> syn::Result<Literal> {
+    let cstr = CString::new(lit_str.value()).map_err(|err| {
+        let pos = err.nul_position();
+        syn::Error::new_spanned(
+            lit_str,
+            format!(
+                "\
+                unexpected null char in #[symbol] {} at position {}\n\
+                hint: symbol {} must be a valid C string and cannot contain null characters",
+                what, pos, what
+            ),
+        )
+    })?;
+    Ok(Literal::c_string(&cstr))
+}
+
+fn extract_symbol_info(field: &syn::Field, attr: &syn::Attribute) -> syn::Result<FieldInfo> {
+    match &attr.meta {
+        syn::Meta::NameValue(kv_pair) => {
+            let name_lit_str = match &kv_pair.value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(lit_str),
+                    ..
+                }) => lit_str,
+                expr => return Err(syn::Error::new_spanned(expr, SYMBOL_FORMAT_HINT)),
+            };
+            Ok(FieldInfo::Symbol {
+                str_token: name_lit_str.token(),
+                cstr_token: lit_str_to_cstr(name_lit_str, "name")?,
+                version: None,
+                name_token: name_lit_str.token(),
+                version_token: None,
+            })
+        }
+        syn::Meta::List(_) => {
+            let mut name_lit_str: Option<syn::LitStr> = None;
+            let mut version_lit_str: Option<syn::LitStr> = None;
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    name_lit_str = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("version") {
+                    version_lit_str = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error(SYMBOL_FORMAT_HINT))
+                }
+            })?;
+
+            let name = name_lit_str
+                .as_ref()
+                .map_or_else(|| field.ident.as_ref().unwrap().to_string(), syn::LitStr::value);
+            let cstr_token = match &name_lit_str {
+                Some(lit_str) => lit_str_to_cstr(lit_str, "name")?,
+                None => Literal::c_string(
+                    &CString::new(name.clone()).expect("valid identifiers shouldn't contain null characters"),
+                ),
+            };
+            match &version_lit_str {
+                Some(lit_str) => Ok(FieldInfo::Symbol {
+                    str_token: Literal::string(&format!("{}@{}", name, lit_str.value())),
+                    cstr_token,
+                    version: Some(lit_str_to_cstr(lit_str, "version")?),
+                    name_token: Literal::string(&name),
+                    version_token: Some(lit_str.token()),
+                }),
+                None => Ok(FieldInfo::Symbol {
+                    str_token: Literal::string(&name),
+                    cstr_token,
+                    version: None,
+                    name_token: Literal::string(&name),
+                    version_token: None,
+                }),
+            }
+        }
+        syn::Meta::Path(path) => Err(syn::Error::new_spanned(path, SYMBOL_FORMAT_HINT)),
+    }
+}
+
 fn extract_field_info(field: &syn::Field) -> syn::Result<FieldInfo> {
     let mut current_info: Option<FieldInfo> = None;
     for attr in &field.attrs {
@@ -93,37 +437,7 @@ fn extract_field_info(field: &syn::Field) -> syn::Result<FieldInfo> {
                     "Only one of #[symbol] and #[subgroup] can be applied to a member",
                 ));
             }
-            let kv_pair = attr.meta.require_name_value()?;
-            let name_lit_str = match &kv_pair.value {
-                syn::Expr::Lit(syn::ExprLit {
-                    lit: syn::Lit::Str(lit_str),
-                    ..
-                }) => lit_str,
-                expr => {
-                    return Err(syn::Error::new_spanned(
-                        expr, "\
-                        #[symbol] expects the format: #[symbol = \"symbol_name\"] where \"symbol_name\" \
-                        is a valid C string",
-                    ))
-                }
-            };
-            let name_cstr = CString::new(name_lit_str.value()).map_err(|err| {
-                let pos = err.nul_position();
-                syn::Error::new_spanned(
-                    &name_lit_str,
-                    format!(
-                        "\
-                        unexpected null char in #[symbol] name at position {}\n\
-                        hint: symbol name must be a valid C string and cannot contain null characters",
-                        pos
-                    ),
-                )
-            })?;
-            let name_lit_cstr = Literal::c_string(&name_cstr);
-            current_info = Some(FieldInfo::Symbol {
-                str_token: name_lit_str.token(),
-                cstr_token: name_lit_cstr,
-            });
+            current_info = Some(extract_symbol_info(field, attr)?);
         } else if attr.path().is_ident("subgroup") {
             if current_info.is_some() {
                 return Err(syn::Error::new_spanned(
@@ -140,7 +454,13 @@ fn extract_field_info(field: &syn::Field) -> syn::Result<FieldInfo> {
         let name_cstr = CString::new(name_str.clone()).expect(
             "valid identifiers shouldn't contain null characters",
         );
-        FieldInfo::Symbol { str_token: Literal::string(&name_str), cstr_token: Literal::c_string(&name_cstr) }
+        FieldInfo::Symbol {
+            str_token: Literal::string(&name_str),
+            cstr_token: Literal::c_string(&name_cstr),
+            version: None,
+            name_token: Literal::string(&name_str),
+            version_token: None,
+        }
     });
     Ok(info)
 }
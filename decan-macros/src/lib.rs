@@ -25,9 +25,35 @@ mod derive_symbol_group;
 /// ## Features
 /// - Functions will be loaded by field name by default. To override this,
 ///   add `#[symbol = "entry_point"]` to specify an entry point.
+/// - On glibc, a specific symbol version can be requested using
+///   `#[symbol(name = "memcpy", version = "GLIBC_2.14")]`. The name may be omitted,
+///   in which case the field name is used. Other targets reject versioned symbols
+///   with a compile error.
 /// - `SymbolGroup`s can contain other `SymbolGroup`s. Annotate these
 ///   members with `#[subgroup]` to inform the macro.
-#[proc_macro_derive(SymbolGroup, attributes(symbol, subgroup))]
+/// - The derive also generates the group's [branded][decan::SymbolGroup::Branded] form,
+///   a struct named after the group with a `Branded` suffix (`MyLibraryBranded<'lib>` above),
+///   with the same fields and visibilities. [`decan::can::Can`] and
+///   [`decan::borrow::SymbolGroupRef`] hand out symbols through it, so that no symbol can
+///   outlive its library.
+/// - Adding `#[symbol_group(accessors)]` to the struct generates a method on the branded
+///   form for each member, with the visibility of the struct, so that the fields
+///   themselves can be kept private:
+///   ```rust
+///   # use decan::SymbolGroup;
+///   #[derive(SymbolGroup)]
+///   #[symbol_group(accessors)]
+///   pub struct MyLibrary {
+///       function: extern "C" fn(u32) -> u32,
+///   }
+///   ```
+/// - Adding `#[symbol_group(strict)]` makes [`decan::SymbolGroup::load`] check that every
+///   symbol is defined by the library itself, rather than one of its dependencies or an
+///   interposing library. See [`decan::Symbol::load_strict_from`].
+/// - The derive also describes each symbol's name, version, kind (function or data) and
+///   optional-ness through [`decan::SymbolGroup::symbols`], which is used by
+///   [`decan::SymbolGroup::verify_file`] to check a library file without loading it.
+#[proc_macro_derive(SymbolGroup, attributes(symbol, subgroup, symbol_group))]
 pub fn derive_symbol_group(item: TokenStream) -> TokenStream {
     let item = parse_macro_input!(item as syn::DeriveInput);
 
@@ -1,3 +1,6 @@
+use std::sync::atomic::{AtomicI32, Ordering};
+
+static COUNTER: AtomicI32 = AtomicI32::new(0);
 
 #[no_mangle]
 pub extern "C" fn print_message() {
@@ -7,4 +10,24 @@ pub extern "C" fn print_message() {
 #[no_mangle]
 pub extern "C" fn square_int(x: i32) -> i32 {
     x * x
-}
\ No newline at end of file
+}
+
+#[no_mangle]
+pub extern "C" fn increment_counter() -> i32 {
+    COUNTER.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+/// Hands the counter to the next version of the library when reloading.
+#[no_mangle]
+pub extern "C" fn save_state() -> *mut i32 {
+    Box::into_raw(Box::new(COUNTER.load(Ordering::SeqCst)))
+}
+
+/// Restores the counter saved by the previous version of the library.
+///
+/// # Safety
+/// `state` must have been returned by [`save_state`].
+#[no_mangle]
+pub unsafe extern "C" fn restore_state(state: *mut i32) {
+    COUNTER.store(*Box::from_raw(state), Ordering::SeqCst);
+}
@@ -8,7 +8,10 @@
 
 use std::{ffi::CStr, marker::PhantomData, ops::Deref};
 
-use crate::{LibraryHandle, Symbol, SymbolError, SymbolGroup, SymbolGroupError};
+use crate::{
+    traits::load_with_suggestions, LibraryHandle, ProbeReport, Symbol, SymbolError, SymbolGroup,
+    SymbolGroupErrors,
+};
 
 /// Extension trait for borrowing symbols from a library handle.
 pub trait LibraryBorrowExt: LibraryHandle {
@@ -25,7 +28,38 @@ pub trait LibraryBorrowExt: LibraryHandle {
     ) -> Result<SymbolRef<'a, T>, SymbolError> {
         let data = T::load_from(self.as_raw(), name)?;
         Ok(SymbolRef {
-            data,
+            data: data.brand(),
+            marker: PhantomData,
+        })
+    }
+
+    /// Borrows a specific version of a symbol from a library (e.g. `memcpy@GLIBC_2.14`).
+    /// # Safety
+    /// See [`LibraryBorrowExt::borrow_symbol`].
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    unsafe fn borrow_versioned_symbol<'a, T: Symbol>(
+        &'a self,
+        name: &CStr,
+        version: &CStr,
+    ) -> Result<SymbolRef<'a, T>, SymbolError> {
+        let data = T::load_versioned_from(self.as_raw(), name, version)?;
+        Ok(SymbolRef {
+            data: data.brand(),
+            marker: PhantomData,
+        })
+    }
+
+    /// Borrows a symbol from a library, requiring it to be defined by the library
+    /// itself rather than one of its dependencies. See [`Symbol::load_strict_from`].
+    /// # Safety
+    /// See [`LibraryBorrowExt::borrow_symbol`].
+    unsafe fn borrow_symbol_strict<'a, T: Symbol>(
+        &'a self,
+        name: &CStr,
+    ) -> Result<SymbolRef<'a, T>, SymbolError> {
+        let data = T::load_strict_from(self.as_raw(), name)?;
+        Ok(SymbolRef {
+            data: data.brand(),
             marker: PhantomData,
         })
     }
@@ -39,25 +73,48 @@ pub trait LibraryBorrowExt: LibraryHandle {
     /// through multiple mutable references, as this is considered UB in Rust.
     unsafe fn borrow_group<'a, T: SymbolGroup>(
         &'a self,
-    ) -> Result<SymbolGroupRef<'a, T>, SymbolGroupError> {
-        let group = T::load(self.as_raw())?;
+    ) -> Result<SymbolGroupRef<'a, T>, SymbolGroupErrors> {
+        let group = load_with_suggestions(self.as_raw(), T::load)?;
         Ok(SymbolGroupRef {
-            group,
+            group: group.brand(),
             marker: PhantomData,
         })
     }
+
+    /// Borrows a symbol group from a library, requiring every symbol to be defined
+    /// by the library itself. See [`SymbolGroup::load_strict`].
+    /// # Safety
+    /// See [`LibraryBorrowExt::borrow_group`].
+    unsafe fn borrow_group_strict<'a, T: SymbolGroup>(
+        &'a self,
+    ) -> Result<SymbolGroupRef<'a, T>, SymbolGroupErrors> {
+        let group = load_with_suggestions(self.as_raw(), T::load_strict)?;
+        Ok(SymbolGroupRef {
+            group: group.brand(),
+            marker: PhantomData,
+        })
+    }
+
+    /// Checks which symbols of a symbol group this library provides,
+    /// without loading the group. See [`SymbolGroup::probe`].
+    fn probe_group<T: SymbolGroup>(&self) -> ProbeReport {
+        unsafe { T::probe(self.as_raw()) }
+    }
 }
 
 impl<T> LibraryBorrowExt for T where T: LibraryHandle {}
 
 /// A borrowed reference to a symbol.
+/// 
+/// This dereferences to the [branded][Symbol::Branded] form of the symbol,
+/// so copying it out still cannot outlive the library.
 pub struct SymbolRef<'a, T: Symbol> {
-    data: T,
+    data: T::Branded<'a>,
     marker: PhantomData<&'a ()>,
 }
 
 impl<'a, T: Symbol> Deref for SymbolRef<'a, T> {
-    type Target = T;
+    type Target = T::Branded<'a>;
 
     fn deref(&self) -> &Self::Target {
         &self.data
@@ -65,13 +122,28 @@ impl<'a, T: Symbol> Deref for SymbolRef<'a, T> {
 }
 
 /// A borrowed reference to a symbol group.
+///
+/// This dereferences to the [branded][SymbolGroup::Branded] form of the group,
+/// so symbols copied out of it still cannot outlive the library:
+/// ```compile_fail,E0505
+/// # use decan::{borrow::LibraryBorrowExt, raw::Library, SymbolGroup};
+/// # #[derive(SymbolGroup)]
+/// # struct Libc {
+/// #     abs: extern "C" fn(i32) -> i32,
+/// # }
+/// let lib = Library::load_by_name("libc.so.6").unwrap();
+/// let libc = unsafe { lib.borrow_group::<Libc>().unwrap() };
+/// let abs = libc.abs;
+/// drop(lib);
+/// abs.call(-2);
+/// ```
 pub struct SymbolGroupRef<'a, T: SymbolGroup> {
-    group: T,
+    group: T::Branded<'a>,
     marker: PhantomData<&'a ()>,
 }
 
 impl<'a, T: SymbolGroup> Deref for SymbolGroupRef<'a, T> {
-    type Target = T;
+    type Target = T::Branded<'a>;
 
     fn deref(&self) -> &Self::Target {
         &self.group
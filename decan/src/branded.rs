@@ -0,0 +1,114 @@
+use std::{marker::PhantomData, ptr::NonNull};
+
+use crate::util;
+
+/// A function pointer that cannot outlive the library it was loaded from.
+///
+/// Unlike bare `extern "C" fn` types, a `Func` carries the lifetime of its library,
+/// so the borrow checker rejects any attempt to call it after the library is unloaded:
+/// ```compile_fail
+/// # use decan::{borrow::LibraryBorrowExt, raw::Library};
+/// let lib = Library::load_by_name("libc.so.6").unwrap();
+/// let abs = unsafe { *lib.borrow_symbol::<extern "C" fn(i32) -> i32>(c"abs").unwrap() };
+/// drop(lib);
+/// abs.call(-2);
+/// ```
+#[derive(Clone, Copy)]
+pub struct Func<'lib, F> {
+    func: F,
+    marker: PhantomData<&'lib ()>,
+}
+
+impl<'lib, F: Copy> Func<'lib, F> {
+    /// Brands a function pointer with the lifetime `'lib`.
+    /// # Safety
+    /// The caller is responsible for ensuring that the function remains valid for `'lib`.
+    pub unsafe fn new(func: F) -> Self {
+        Self {
+            func,
+            marker: PhantomData,
+        }
+    }
+
+    /// Extracts the raw function pointer, discarding its lifetime.
+    /// # Safety
+    /// The caller is responsible for ensuring that the function is not called
+    /// after the library it came from is unloaded.
+    pub unsafe fn into_raw(self) -> F {
+        self.func
+    }
+}
+
+macro_rules! impl_func_call {
+    ($($types:ident),* $(,)?) => {
+        impl<'lib, R, $($types),*> Func<'lib, extern "C" fn($($types),*) -> R> {
+            /// Calls the function.
+            #[allow(non_snake_case, clippy::too_many_arguments)]
+            #[inline(always)]
+            pub fn call(&self, $($types: $types),*) -> R {
+                (self.func)($($types),*)
+            }
+        }
+
+        impl<'lib, R, $($types),*> Func<'lib, unsafe extern "C" fn($($types),*) -> R> {
+            /// Calls the function.
+            /// # Safety
+            /// The caller must uphold the safety contract of the underlying function.
+            #[allow(non_snake_case, clippy::too_many_arguments)]
+            #[inline(always)]
+            pub unsafe fn call(&self, $($types: $types),*) -> R {
+                (self.func)($($types),*)
+            }
+        }
+    };
+}
+
+util::variadic_0_16!(impl_func_call);
+#[cfg(feature = "variadic_32")]
+util::variadic_17_32!(impl_func_call);
+
+/// A pointer to data that cannot outlive the library it was loaded from.
+pub struct Data<'lib, T> {
+    ptr: NonNull<T>,
+    marker: PhantomData<&'lib ()>,
+}
+
+impl<T> Clone for Data<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for Data<'_, T> {}
+
+impl<'lib, T> Data<'lib, T> {
+    /// Brands a data pointer with the lifetime `'lib`.
+    /// # Safety
+    /// The caller is responsible for ensuring that the pointer remains valid for `'lib`.
+    pub unsafe fn new(ptr: NonNull<T>) -> Self {
+        Self {
+            ptr,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the raw pointer to the data.
+    pub fn as_ptr(&self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+
+    /// Returns a shared reference to the data.
+    /// # Safety
+    /// See [`NonNull::as_ref`].
+    pub unsafe fn as_ref(&self) -> &'lib T {
+        self.ptr.as_ref()
+    }
+
+    /// Returns a mutable reference to the data.
+    /// # Safety
+    /// See [`NonNull::as_mut`]. In particular, the caller must ensure
+    /// that no other reference to the data exists while this one is alive.
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn as_mut(&self) -> &'lib mut T {
+        &mut *self.ptr.as_ptr()
+    }
+}
@@ -7,9 +7,20 @@
 //! While this is memory-safe, it creates a more permanent association between the library and its symbols.
 //! If you only need to load symbols temporarily, you may want to use the [`borrow`][`crate::borrow`] module.
 
-use std::{mem::ManuallyDrop, ops::Deref, path::Path};
+#[cfg(not(target_os = "linux"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{
+    ffi::{c_void, OsStr},
+    mem::ManuallyDrop,
+    path::{Path, PathBuf},
+};
 
-use crate::{raw::{self, Library}, LibraryHandle, LoadOrSymbolGroupError, SymbolGroup, SymbolGroupError};
+use crate::{
+    raw::{self, Library, LoadOptions},
+    traits::load_with_suggestions,
+    CloseError, LibraryHandle, LoadError, LoadMode, LoadOrSymbolGroupError, ReloadError, Symbol, SymbolGroup,
+    SymbolGroupErrors,
+};
 
 /// A [`Can`] that owns its library handle.
 pub type OwningCan<G> = Can<Library, G>;
@@ -18,9 +29,53 @@ pub type NonOwningCan<G> = Can<ManuallyDrop<Library>, G>;
 
 /// A library together with a set of symbols. The symbols do not outlive the library
 /// because they die with it.
+///
+/// The symbols are accessed through [`Can::symbols`], which returns the
+/// [branded][SymbolGroup::Branded] form of the group. Each symbol borrows the can, so
+/// the borrow checker rejects any use of a symbol after the can is dropped:
+/// ```no_run
+/// # use decan::{can::Can, SymbolGroup};
+/// #[derive(SymbolGroup)]
+/// struct Testlib {
+///     square_int: extern "C" fn(i32) -> i32,
+/// }
+///
+/// let can = unsafe { Can::<_, Testlib>::load("libtestlib.so").unwrap() };
+/// let square = can.symbols().square_int;
+/// assert_eq!(square.call(2), 4);
+/// ```
+/// ```compile_fail,E0505
+/// # use decan::{can::Can, SymbolGroup};
+/// # #[derive(SymbolGroup)]
+/// # struct Testlib {
+/// #     square_int: extern "C" fn(i32) -> i32,
+/// # }
+/// let can = unsafe { Can::<_, Testlib>::load("libtestlib.so").unwrap() };
+/// let square = can.symbols().square_int;
+/// drop(can);
+/// square.call(2);
+/// ```
 pub struct Can<H: LibraryHandle, G: SymbolGroup> {
     handle: H,
     symbols: G,
+    source: Option<Source>,
+}
+
+/// Where a can's library was loaded from, so that it can be reloaded.
+struct Source {
+    path: PathBuf,
+    options: LoadOptions,
+    /// The shadow copy currently loaded, if it couldn't be removed after loading.
+    shadow: Option<PathBuf>,
+}
+
+impl Drop for Source {
+    fn drop(&mut self) {
+        // the library is closed by now, so the shadow copy can be removed even on Windows
+        if let Some(shadow) = self.shadow.take() {
+            let _ = std::fs::remove_file(shadow);
+        }
+    }
 }
 
 impl<H: LibraryHandle, G: SymbolGroup> Can<H, G> {
@@ -28,9 +83,18 @@ impl<H: LibraryHandle, G: SymbolGroup> Can<H, G> {
     /// # Safety
     /// The caller is responsible for ensuring that the symbols specified in `G`
     /// match exported library symbols with the correct type.
-    pub unsafe fn with_handle(handle: H) -> Result<Self, SymbolGroupError> {
-        let symbols = G::load(handle.as_raw())?;
-        Ok(Self { handle, symbols })
+    pub unsafe fn with_handle(handle: H) -> Result<Self, SymbolGroupErrors> {
+        let symbols = load_with_suggestions(handle.as_raw(), G::load)?;
+        Ok(Self { handle, symbols, source: None })
+    }
+
+    /// Creates a can using an existing handle, requiring every symbol to be defined
+    /// by the library itself. See [`SymbolGroup::load_strict`].
+    /// # Safety
+    /// See [`Can::with_handle`].
+    pub unsafe fn with_handle_strict(handle: H) -> Result<Self, SymbolGroupErrors> {
+        let symbols = load_with_suggestions(handle.as_raw(), G::load_strict)?;
+        Ok(Self { handle, symbols, source: None })
     }
 }
 
@@ -40,7 +104,198 @@ impl<G: SymbolGroup> Can<Library, G> {
     /// The caller is responsible for ensuring that the symbols specified in `G`
     /// match exported library symbols with the correct type.
     pub unsafe fn load<P: AsRef<Path>>(path: P) -> Result<Self, LoadOrSymbolGroupError> {
-        Self::with_handle(Library::load(path)?).map_err(Into::into)
+        Self::load_with(path, &LoadOptions::new())
+    }
+
+    /// Loads a can from a specified path using the provided options.
+    /// # Safety
+    /// The caller is responsible for ensuring that the symbols specified in `G`
+    /// match exported library symbols with the correct type.
+    pub unsafe fn load_with<P: AsRef<Path>>(
+        path: P,
+        options: &LoadOptions,
+    ) -> Result<Self, LoadOrSymbolGroupError> {
+        // resolve the path now, so that reloading isn't affected by later changes to
+        // the working directory or to symlinks along the path
+        let path = path
+            .as_ref()
+            .canonicalize()
+            .map_err(|err| LoadError::from(err).in_mode(LoadMode::Path, path.as_ref()))?;
+        let mut can = Self::with_handle(Library::load_with(&path, options)?)?;
+        can.source = Some(Source {
+            path,
+            options: *options,
+            shadow: None,
+        });
+        Ok(can)
+    }
+
+    /// Loads a can from a specified path into an isolated namespace.
+    /// See [`Library::load_in`] for details.
+    /// # Safety
+    /// The caller is responsible for ensuring that the symbols specified in `G`
+    /// match exported library symbols with the correct type.
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    pub unsafe fn load_in<P: AsRef<Path>>(
+        namespace: &raw::Namespace,
+        path: P,
+    ) -> Result<Self, LoadOrSymbolGroupError> {
+        Self::with_handle(Library::load_in(namespace, path)?).map_err(Into::into)
+    }
+
+    /// Loads a can by library name, searching the system search path.
+    /// See [`Library::load_by_name`] for details.
+    /// # Safety
+    /// The caller is responsible for ensuring that the symbols specified in `G`
+    /// match exported library symbols with the correct type.
+    pub unsafe fn load_by_name<S: AsRef<OsStr>>(name: S) -> Result<Self, LoadOrSymbolGroupError> {
+        Self::with_handle(Library::load_by_name(name)?).map_err(Into::into)
+    }
+
+    /// Loads a can by library name using the provided options.
+    /// # Safety
+    /// The caller is responsible for ensuring that the symbols specified in `G`
+    /// match exported library symbols with the correct type.
+    pub unsafe fn load_by_name_with<S: AsRef<OsStr>>(
+        name: S,
+        options: &LoadOptions,
+    ) -> Result<Self, LoadOrSymbolGroupError> {
+        Self::with_handle(Library::load_by_name_with(name, options)?).map_err(Into::into)
+    }
+
+    /// Loads a can from an ELF image held in memory.
+    /// See [`Library::load_from_bytes`] for details.
+    /// # Safety
+    /// The caller is responsible for ensuring that the symbols specified in `G`
+    /// match exported library symbols with the correct type.
+    #[cfg(target_os = "linux")]
+    pub unsafe fn load_from_bytes(image: &[u8]) -> Result<Self, LoadOrSymbolGroupError> {
+        Self::with_handle(Library::load_from_bytes(image)?).map_err(Into::into)
+    }
+
+    /// Loads a can from an ELF image held in memory using the provided options.
+    /// # Safety
+    /// The caller is responsible for ensuring that the symbols specified in `G`
+    /// match exported library symbols with the correct type.
+    #[cfg(target_os = "linux")]
+    pub unsafe fn load_from_bytes_with(
+        image: &[u8],
+        options: &LoadOptions,
+    ) -> Result<Self, LoadOrSymbolGroupError> {
+        Self::with_handle(Library::load_from_bytes_with(image, options)?).map_err(Into::into)
+    }
+}
+
+impl<G: SymbolGroup> Can<Library, G> {
+    /// Creates a can from a library only if it is already loaded.
+    /// See [`Library::get_if_loaded`] for details.
+    /// # Safety
+    /// The caller is responsible for ensuring that the symbols specified in `G`
+    /// match exported library symbols with the correct type.
+    pub unsafe fn get_if_loaded<S: AsRef<OsStr>>(name: S) -> Result<Option<Self>, LoadOrSymbolGroupError> {
+        Library::get_if_loaded(name)?
+            .map(|lib| Self::with_handle(lib).map_err(Into::into))
+            .transpose()
+    }
+
+    /// Closes the library, returning any error reported by the dynamic linker.
+    /// See [`Library::close`] for details.
+    pub fn close(self) -> Result<(), CloseError> {
+        let Self { handle, symbols, .. } = self;
+        drop(symbols);
+        handle.close()
+    }
+
+    /// Loads a can from an in-memory copy of the file at `path` (see [`Library::load_from_bytes`]),
+    /// so that the file can be replaced while the library is loaded, without writing anything
+    /// next to it.
+    #[cfg(target_os = "linux")]
+    pub(crate) unsafe fn load_in_memory(path: &Path, options: &LoadOptions) -> Result<Self, ReloadError> {
+        let image = std::fs::read(path).map_err(|err| ReloadError::Read {
+            path: path.to_owned(),
+            source: err,
+        })?;
+        let handle = Library::load_from_bytes_with(&image, options)?;
+        let symbols = load_with_suggestions(handle.as_raw(), G::load)?;
+        Ok(Self {
+            handle,
+            symbols,
+            source: Some(Source {
+                path: path.to_owned(),
+                options: *options,
+                shadow: None,
+            }),
+        })
+    }
+
+    /// Reloads the library from the file it was loaded from, e.g. after it was rebuilt.
+    ///
+    /// Dynamic linkers reuse libraries that are already loaded from the same path, and
+    /// overwriting a loaded library can crash the process. Instead, a copy of the file is
+    /// loaded and resolved as `G`: on Linux, the file is read into memory and loaded from
+    /// there (see [`Library::load_from_bytes`]), and elsewhere it is copied to a unique
+    /// shadow path in [`std::env::temp_dir`]. Nothing is written next to the library, but
+    /// its `$ORIGIN`-relative dependencies don't resolve from the copy. The previous
+    /// library is only closed once the new one has loaded successfully; otherwise the
+    /// can is left unchanged.
+    ///
+    /// If the previous library exports `save_state` (`extern "C" fn() -> *mut c_void`),
+    /// its result is passed to the new library's `restore_state`
+    /// (`extern "C" fn(*mut c_void)`) before the previous library is closed. The pointer
+    /// is opaque to this crate, so the plugin is responsible for ownership of the state.
+    /// `save_state` is called on the current thread without any synchronization, so if other
+    /// threads may be calling into the library at the same time (as with [`SwapCan::reload`][crate::SwapCan::reload]),
+    /// it must be thread-safe.
+    ///
+    /// Only cans created with [`Can::load`] or [`Can::load_with`] can be reloaded. They are
+    /// reloaded with the same options, from the canonical path the can was first loaded from.
+    /// # Safety
+    /// The caller is responsible for ensuring that the symbols specified in `G`
+    /// match exported library symbols with the correct type, and that no symbols
+    /// from the previous library are in use, since it may be unloaded.
+    pub unsafe fn reload(&mut self) -> Result<(), ReloadError> {
+        let Self { handle, symbols, source } = std::mem::replace(self, self.reloaded()?);
+        // drop the old symbols before the library they came from, then its shadow copy
+        drop(symbols);
+        drop(handle);
+        drop(source);
+        Ok(())
+    }
+
+    /// Loads a new version of this can's library like [`Can::reload`], handing over its
+    /// state, but leaves this can untouched.
+    pub(crate) unsafe fn reloaded(&self) -> Result<Self, ReloadError> {
+        let source = self.source.as_ref().ok_or(ReloadError::NoSource)?;
+        #[cfg(target_os = "linux")]
+        let reloaded = Self::load_in_memory(&source.path, &source.options)?;
+        #[cfg(not(target_os = "linux"))]
+        let reloaded = {
+            let (handle, symbols, shadow) = load_shadow_copy(&source.path, &source.options)?;
+            Self {
+                handle,
+                symbols,
+                source: Some(Source {
+                    path: source.path.clone(),
+                    options: source.options,
+                    shadow,
+                }),
+            }
+        };
+
+        type SaveState = Option<extern "C" fn() -> *mut c_void>;
+        type RestoreState = Option<extern "C" fn(*mut c_void)>;
+        if let Ok(Some(save_state)) = SaveState::load_from(self.handle.as_raw(), c"save_state") {
+            let state = save_state();
+            match RestoreState::load_from(reloaded.handle.as_raw(), c"restore_state") {
+                Ok(Some(restore_state)) => restore_state(state),
+                _ => log::warn!(
+                    "`{}` saved its state, but the new version has no `restore_state`",
+                    source.path.display()
+                ),
+            }
+        }
+
+        Ok(reloaded)
     }
 }
 
@@ -49,7 +304,7 @@ impl<G: SymbolGroup> Can<ManuallyDrop<Library>, G> {
     /// # Safety
     /// The caller is responsible for ensuring that the symbols specified in `G`
     /// match exported library symbols with the correct type.
-    pub unsafe fn wrap_raw(raw_handle: raw::Handle) -> Result<Self, SymbolGroupError> {
+    pub unsafe fn wrap_raw(raw_handle: raw::Handle) -> Result<Self, SymbolGroupErrors> {
         Self::with_handle(Library::wrap_raw(raw_handle))
     }
 }
@@ -63,11 +318,48 @@ impl<H: LibraryHandle, G: SymbolGroup> LibraryHandle for Can<H, G> {
     }
 }
 
-impl<H: LibraryHandle, G: SymbolGroup> Deref for Can<H, G> {
-    type Target = G;
+impl<H: LibraryHandle, G: SymbolGroup> Can<H, G> {
+    /// Returns the can's symbols, which cannot outlive it.
+    pub fn symbols(&self) -> G::Branded<'_> {
+        // SAFETY: the library stays loaded while the can is borrowed
+        unsafe { self.symbols.brand() }
+    }
+}
+
+/// Copies `path` to a shadow path and loads `G` from the copy.
+///
+/// Also returns the shadow path if it couldn't be removed after loading, which is the case on Windows.
+#[cfg(not(target_os = "linux"))]
+unsafe fn load_shadow_copy<G: SymbolGroup>(
+    path: &Path,
+    options: &LoadOptions,
+) -> Result<(Library, G, Option<PathBuf>), ReloadError> {
+    let shadow = shadow_path(path);
+    std::fs::copy(path, &shadow).map_err(|err| ReloadError::ShadowCopy {
+        from: path.to_owned(),
+        to: shadow.clone(),
+        source: err,
+    })?;
+
+    let loaded = Library::load_with(&shadow, options)
+        .map_err(ReloadError::from)
+        .and_then(|library| Ok((load_with_suggestions(library.as_raw(), G::load)?, library)));
+    let removed = std::fs::remove_file(&shadow).is_ok();
+    let (symbols, library) = loaded?;
+    Ok((library, symbols, (!removed).then_some(shadow)))
+}
+
+/// Creates a unique path in the temporary directory to load a shadow copy of `path` from.
+#[cfg(not(target_os = "linux"))]
+fn shadow_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
 
-    /// Dereferences to the underlying symbol group.
-    fn deref(&self) -> &Self::Target {
-        &self.symbols
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut name = format!("{stem}.reload-{}-{count}", std::process::id());
+    if let Some(extension) = path.extension() {
+        name.push('.');
+        name.push_str(&extension.to_string_lossy());
     }
+    std::env::temp_dir().join(name)
 }
\ No newline at end of file
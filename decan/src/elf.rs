@@ -0,0 +1,948 @@
+//! A small reader for ELF shared objects.
+//!
+//! This parses a library's dynamic section, symbol table, symbol versions and
+//! hash tables from its file, without mapping it into memory or running any of its code.
+//! ```no_run
+//! # use decan::elf::ElfLibrary;
+//! let lib = ElfLibrary::open("/usr/lib/libc.so.6").unwrap();
+//! for sym in lib.exports() {
+//!     println!("{} {:?} {:?}", sym.name(), sym.version(), sym.kind());
+//! }
+//! ```
+
+use std::{fs, path::Path};
+
+use crate::ElfError;
+
+const ELF_MAGIC: &[u8; 4] = b"\x7fELF";
+
+const EI_CLASS: usize = 4;
+const EI_DATA: usize = 5;
+const ELFCLASS32: u8 = 1;
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const ELFDATA2MSB: u8 = 2;
+
+const SHT_DYNSYM: u32 = 11;
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+
+const DT_NULL: u64 = 0;
+const DT_NEEDED: u64 = 1;
+const DT_HASH: u64 = 4;
+const DT_STRTAB: u64 = 5;
+const DT_SYMTAB: u64 = 6;
+const DT_STRSZ: u64 = 10;
+const DT_SYMENT: u64 = 11;
+const DT_SONAME: u64 = 14;
+const DT_RPATH: u64 = 15;
+const DT_RUNPATH: u64 = 29;
+const DT_GNU_HASH: u64 = 0x6fff_fef5;
+const DT_VERSYM: u64 = 0x6fff_fff0;
+const DT_VERDEF: u64 = 0x6fff_fffc;
+const DT_VERDEFNUM: u64 = 0x6fff_fffd;
+const DT_VERNEED: u64 = 0x6fff_fffe;
+const DT_VERNEEDNUM: u64 = 0x6fff_ffff;
+
+const SHN_UNDEF: u16 = 0;
+const SHN_LORESERVE: u16 = 0xff00;
+
+const VER_FLG_BASE: u16 = 0x1;
+const VERSYM_HIDDEN: u16 = 0x8000;
+const VERSYM_GLOBAL: u16 = 1;
+
+/// The word size of an ELF file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElfClass {
+    /// A 32-bit file.
+    Elf32,
+    /// A 64-bit file.
+    Elf64,
+}
+
+impl ElfClass {
+    /// The class of the current process.
+    #[cfg(target_pointer_width = "64")]
+    pub const HOST: Self = Self::Elf64;
+    /// The class of the current process.
+    #[cfg(not(target_pointer_width = "64"))]
+    pub const HOST: Self = Self::Elf32;
+}
+
+/// The byte order of an ELF file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endianness {
+    /// Least significant byte first.
+    Little,
+    /// Most significant byte first.
+    Big,
+}
+
+impl Endianness {
+    /// The byte order of the current process.
+    #[cfg(target_endian = "little")]
+    pub const HOST: Self = Self::Little;
+    /// The byte order of the current process.
+    #[cfg(target_endian = "big")]
+    pub const HOST: Self = Self::Big;
+}
+
+/// The binding of a symbol, i.e. its visibility to other objects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolBinding {
+    /// The symbol is only visible inside the object defining it.
+    Local,
+    /// The symbol is visible to all objects.
+    Global,
+    /// Like [`SymbolBinding::Global`], but with lower precedence.
+    Weak,
+    /// Any other (OS- or processor-specific) binding.
+    Other(u8),
+}
+
+impl SymbolBinding {
+    fn from_info(info: u8) -> Self {
+        match info >> 4 {
+            0 => Self::Local,
+            1 => Self::Global,
+            2 => Self::Weak,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// The type of a symbol, i.e. what kind of entity it refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolType {
+    /// The type is not specified.
+    NoType,
+    /// A data object, such as a variable.
+    Object,
+    /// A function.
+    Func,
+    /// A section.
+    Section,
+    /// A source file.
+    File,
+    /// An uninitialized common block.
+    Common,
+    /// A thread-local variable.
+    Tls,
+    /// A function whose address is chosen at load time by a resolver function.
+    GnuIfunc,
+    /// Any other (OS- or processor-specific) type.
+    Other(u8),
+}
+
+impl SymbolType {
+    fn from_info(info: u8) -> Self {
+        match info & 0xf {
+            0 => Self::NoType,
+            1 => Self::Object,
+            2 => Self::Func,
+            3 => Self::Section,
+            4 => Self::File,
+            5 => Self::Common,
+            6 => Self::Tls,
+            10 => Self::GnuIfunc,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// A symbol from a library's dynamic symbol table.
+#[derive(Debug, Clone)]
+pub struct ElfSymbol {
+    name: String,
+    version: Option<String>,
+    hidden: bool,
+    value: u64,
+    size: u64,
+    info: u8,
+    section_index: u16,
+    section: Option<String>,
+}
+
+impl ElfSymbol {
+    /// The name of the symbol.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The version of the symbol (e.g. `GLIBC_2.14`), if it has one.
+    ///
+    /// For defined symbols this is the version they are exported under;
+    /// for undefined symbols it is the version they require.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Returns true if this is the default version of the symbol, i.e. the one
+    /// that is used when linking against the symbol without specifying a version.
+    ///
+    /// Symbols without a version are always the default.
+    pub fn is_default_version(&self) -> bool {
+        !self.hidden
+    }
+
+    /// The value of the symbol, usually its address relative to the library's base address.
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// The size of the symbol in bytes, or 0 if unknown.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The type of the symbol.
+    pub fn kind(&self) -> SymbolType {
+        SymbolType::from_info(self.info)
+    }
+
+    /// The binding of the symbol.
+    pub fn binding(&self) -> SymbolBinding {
+        SymbolBinding::from_info(self.info)
+    }
+
+    /// The index of the section the symbol is defined in (the symbol's `st_shndx`).
+    pub fn section_index(&self) -> u16 {
+        self.section_index
+    }
+
+    /// The name of the section the symbol is defined in (e.g. `.text`), if
+    /// it is defined and the library has section headers.
+    pub fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
+    /// Returns true if this symbol is defined by the library,
+    /// as opposed to being imported from another object.
+    pub fn is_defined(&self) -> bool {
+        self.section_index != SHN_UNDEF
+    }
+}
+
+/// The versions a library requires from one of its dependencies (an entry of `.gnu.version_r`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionNeed {
+    file: String,
+    versions: Vec<String>,
+}
+
+impl VersionNeed {
+    /// The name of the dependency, as in its `DT_NEEDED` entry.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The names of the required versions.
+    pub fn versions(&self) -> &[String] {
+        &self.versions
+    }
+}
+
+/// A hash table used by the dynamic linker to look up symbols by name.
+#[derive(Debug, Clone)]
+enum HashTable {
+    /// The System V hash table (`DT_HASH`).
+    SysV { buckets: Vec<u32>, chains: Vec<u32> },
+    /// The GNU hash table (`DT_GNU_HASH`). Only symbols from `symoffset` onwards are hashed.
+    Gnu {
+        symoffset: u32,
+        buckets: Vec<u32>,
+        chains: Vec<u32>,
+    },
+}
+
+impl HashTable {
+    /// Calls `f` with the index of each symbol whose hash matches `name`,
+    /// until it returns true.
+    fn find(&self, name: &str, mut f: impl FnMut(usize) -> bool) -> Option<usize> {
+        match self {
+            Self::SysV { buckets, chains } => {
+                let hash = sysv_hash(name.as_bytes());
+                let mut index = *buckets.get(hash as usize % buckets.len().max(1))?;
+                // index 0 is STN_UNDEF, which ends the chain. A chain visits each symbol at
+                // most once, so a longer walk means a malformed, cyclic chain
+                for _ in 0..chains.len() {
+                    if index == 0 {
+                        return None;
+                    }
+                    if f(index as usize) {
+                        return Some(index as usize);
+                    }
+                    index = *chains.get(index as usize)?;
+                }
+                None
+            }
+            Self::Gnu {
+                symoffset,
+                buckets,
+                chains,
+            } => {
+                let hash = gnu_hash(name.as_bytes());
+                let mut index = *buckets.get(hash as usize % buckets.len().max(1))?;
+                loop {
+                    // buckets and chains come from the file, so a malformed table
+                    // must end the walk rather than wrap around
+                    let chain = *chains.get(index.checked_sub(*symoffset)? as usize)?;
+                    if chain | 1 == hash | 1 && f(index as usize) {
+                        return Some(index as usize);
+                    }
+                    // the low bit marks the end of the chain
+                    if chain & 1 != 0 {
+                        return None;
+                    }
+                    index = index.checked_add(1)?;
+                }
+            }
+        }
+    }
+}
+
+fn sysv_hash(name: &[u8]) -> u32 {
+    name.iter().fold(0u32, |hash, &b| {
+        let hash = (hash << 4).wrapping_add(u32::from(b));
+        let high = hash & 0xf000_0000;
+        (hash ^ (high >> 24)) & !high
+    })
+}
+
+fn gnu_hash(name: &[u8]) -> u32 {
+    name.iter()
+        .fold(5381u32, |hash, &b| hash.wrapping_mul(33).wrapping_add(u32::from(b)))
+}
+
+/// A library parsed from an ELF file.
+#[derive(Debug, Clone)]
+pub struct ElfLibrary {
+    class: ElfClass,
+    endianness: Endianness,
+    machine: u16,
+    needed: Vec<String>,
+    soname: Option<String>,
+    rpath: Option<String>,
+    runpath: Option<String>,
+    symbols: Vec<ElfSymbol>,
+    versions: Vec<String>,
+    version_needs: Vec<VersionNeed>,
+    hash_table: Option<HashTable>,
+}
+
+impl ElfLibrary {
+    /// Reads and parses the ELF file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ElfError> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    /// Parses an ELF file from its contents.
+    pub fn parse(data: &[u8]) -> Result<Self, ElfError> {
+        let reader = Reader::new(data)?;
+        let header = reader.header()?;
+
+        let segments = reader.segments(&header)?;
+        let sections = reader.sections(&header)?;
+
+        let mut library = Self {
+            class: reader.class,
+            endianness: reader.endianness,
+            machine: header.machine,
+            needed: Vec::new(),
+            soname: None,
+            rpath: None,
+            runpath: None,
+            symbols: Vec::new(),
+            versions: Vec::new(),
+            version_needs: Vec::new(),
+            hash_table: None,
+        };
+
+        let Some(dynamic) = segments.iter().find(|seg| seg.kind == PT_DYNAMIC) else {
+            return Ok(library);
+        };
+        let entries = reader.dynamic_entries(dynamic)?;
+        let entry = |tag: u64| entries.iter().find(|(t, _)| *t == tag).map(|&(_, value)| value);
+        let offset_of = |tag: u64| {
+            entry(tag)
+                .map(|addr| reader.addr_to_offset(&segments, addr))
+                .transpose()
+        };
+
+        let strtab = offset_of(DT_STRTAB)?
+            .ok_or(ElfError::Malformed("dynamic section has no string table"))?;
+        let strsz = entry(DT_STRSZ).unwrap_or((data.len() as u64).saturating_sub(strtab));
+        let strtab = reader.slice(strtab, strsz)?;
+
+        for &(tag, value) in &entries {
+            let string = || read_str(strtab, value);
+            match tag {
+                DT_NEEDED => library.needed.push(string()?),
+                DT_SONAME => library.soname = Some(string()?),
+                DT_RPATH => library.rpath = Some(string()?),
+                DT_RUNPATH => library.runpath = Some(string()?),
+                _ => (),
+            }
+        }
+
+        library.hash_table = match (offset_of(DT_GNU_HASH)?, offset_of(DT_HASH)?) {
+            (Some(offset), _) => Some(reader.gnu_hash_table(offset)?),
+            (None, Some(offset)) => Some(reader.sysv_hash_table(offset)?),
+            (None, None) => None,
+        };
+
+        // prefer the section header, but fall back to the dynamic section
+        // (sized using the hash table) if the file has been stripped of sections
+        let symtab = match sections.iter().find(|sec| sec.kind == SHT_DYNSYM) {
+            Some(dynsym) if dynsym.entsize != 0 => {
+                Some((dynsym.offset, dynsym.entsize, dynsym.size / dynsym.entsize))
+            }
+            _ => match (offset_of(DT_SYMTAB)?, &library.hash_table) {
+                (Some(offset), Some(hash_table)) => {
+                    let entsize = entry(DT_SYMENT).unwrap_or(match reader.class {
+                        ElfClass::Elf32 => 16,
+                        ElfClass::Elf64 => 24,
+                    });
+                    Some((offset, entsize, hash_table_len(hash_table)))
+                }
+                _ => None,
+            },
+        };
+        let Some((symtab, entsize, count)) = symtab else {
+            return Ok(library);
+        };
+
+        let mut version_names = Vec::new();
+        if let Some(offset) = offset_of(DT_VERDEF)? {
+            let count = entry(DT_VERDEFNUM).unwrap_or(0);
+            for (index, flags, name) in reader.version_definitions(offset, count, strtab)? {
+                if flags & VER_FLG_BASE == 0 {
+                    library.versions.push(name.clone());
+                }
+                version_names.push((index, name));
+            }
+        }
+        if let Some(offset) = offset_of(DT_VERNEED)? {
+            let count = entry(DT_VERNEEDNUM).unwrap_or(0);
+            for (file, versions) in reader.version_needs(offset, count, strtab)? {
+                let mut need = VersionNeed {
+                    file,
+                    versions: Vec::new(),
+                };
+                for (index, name) in versions {
+                    need.versions.push(name.clone());
+                    version_names.push((index, name));
+                }
+                library.version_needs.push(need);
+            }
+        }
+        let versym = offset_of(DT_VERSYM)?;
+
+        let section_names = reader.section_names(&header, &sections)?;
+
+        // entry 0 is always the null symbol
+        for i in 1..count {
+            let mut symbol = reader.symbol(reader.table_offset(symtab, i, entsize)?, strtab)?;
+            if let Some(versym) = versym {
+                let version = reader.u16(reader.table_offset(versym, i, 2)?)?;
+                symbol.hidden = version & VERSYM_HIDDEN != 0;
+                let index = version & !VERSYM_HIDDEN;
+                if index > VERSYM_GLOBAL {
+                    symbol.version = version_names
+                        .iter()
+                        .find(|(i, _)| *i == index)
+                        .map(|(_, name)| name.clone());
+                }
+            }
+            if symbol.section_index != SHN_UNDEF && symbol.section_index < SHN_LORESERVE {
+                symbol.section = section_names.get(symbol.section_index as usize).cloned();
+            }
+            library.symbols.push(symbol);
+        }
+
+        Ok(library)
+    }
+
+    /// The word size of the library.
+    pub fn class(&self) -> ElfClass {
+        self.class
+    }
+
+    /// The byte order of the library.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// The machine the library was built for (the ELF header's `e_machine`).
+    pub fn machine(&self) -> u16 {
+        self.machine
+    }
+
+    /// The names of the library's direct dependencies (`DT_NEEDED`).
+    pub fn needed(&self) -> &[String] {
+        &self.needed
+    }
+
+    /// The library's `DT_SONAME`, if it has one.
+    pub fn soname(&self) -> Option<&str> {
+        self.soname.as_deref()
+    }
+
+    /// The library's `DT_RPATH`, if it has one.
+    pub fn rpath(&self) -> Option<&str> {
+        self.rpath.as_deref()
+    }
+
+    /// The library's `DT_RUNPATH`, if it has one.
+    pub fn runpath(&self) -> Option<&str> {
+        self.runpath.as_deref()
+    }
+
+    /// All symbols in the library's dynamic symbol table.
+    pub fn symbols(&self) -> &[ElfSymbol] {
+        &self.symbols
+    }
+
+    /// Iterates over the symbols the library defines and makes visible to other objects.
+    pub fn exports(&self) -> impl Iterator<Item = &ElfSymbol> {
+        self.symbols
+            .iter()
+            .filter(|sym| sym.is_defined() && sym.binding() != SymbolBinding::Local)
+    }
+
+    /// Iterates over the symbols the library expects other objects to define.
+    pub fn imports(&self) -> impl Iterator<Item = &ElfSymbol> {
+        self.symbols.iter().filter(|sym| !sym.is_defined())
+    }
+
+    /// Looks up an exported symbol by name, using the library's hash table if it has one.
+    ///
+    /// If the symbol has multiple versions, this returns the default version.
+    pub fn lookup(&self, name: &str) -> Option<&ElfSymbol> {
+        let matches = |sym: &ElfSymbol| {
+            sym.name == name
+                && sym.is_defined()
+                && sym.binding() != SymbolBinding::Local
+                && sym.is_default_version()
+        };
+        match &self.hash_table {
+            Some(hash_table) => {
+                // hash table indices count the null symbol, which isn't stored
+                let index = hash_table.find(name, |index| {
+                    index
+                        .checked_sub(1)
+                        .and_then(|index| self.symbols.get(index))
+                        .is_some_and(matches)
+                })?;
+                self.symbols.get(index - 1)
+            }
+            None => self.symbols.iter().find(|sym| matches(sym)),
+        }
+    }
+
+    /// Looks up an exported symbol by name and version.
+    pub fn lookup_versioned(&self, name: &str, version: &str) -> Option<&ElfSymbol> {
+        self.exports()
+            .find(|sym| sym.name == name && sym.version.as_deref() == Some(version))
+    }
+
+    /// The symbol versions the library defines (`.gnu.version_d`),
+    /// excluding the base version named after the library itself.
+    pub fn versions(&self) -> &[String] {
+        &self.versions
+    }
+
+    /// The symbol versions the library requires from its dependencies (`.gnu.version_r`).
+    pub fn version_needs(&self) -> &[VersionNeed] {
+        &self.version_needs
+    }
+
+    /// Returns true if the library has a GNU or System V hash table.
+    pub fn has_hash_table(&self) -> bool {
+        self.hash_table.is_some()
+    }
+}
+
+/// Returns the number of symbols (including the null symbol) covered by a hash table.
+fn hash_table_len(hash_table: &HashTable) -> u64 {
+    match hash_table {
+        HashTable::SysV { chains, .. } => chains.len() as u64,
+        HashTable::Gnu {
+            symoffset, chains, ..
+        } => u64::from(*symoffset) + chains.len() as u64,
+    }
+}
+
+fn read_str(strtab: &[u8], offset: u64) -> Result<String, ElfError> {
+    let bytes = usize::try_from(offset)
+        .ok()
+        .and_then(|offset| strtab.get(offset..))
+        .ok_or(ElfError::Malformed("string offset out of bounds"))?;
+    let len = bytes
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or(ElfError::Malformed("unterminated string"))?;
+    Ok(String::from_utf8_lossy(&bytes[..len]).into_owned())
+}
+
+struct Header {
+    machine: u16,
+    shstrndx: u16,
+    phoff: u64,
+    phentsize: u16,
+    phnum: u16,
+    shoff: u64,
+    shentsize: u16,
+    shnum: u16,
+}
+
+struct Segment {
+    kind: u32,
+    offset: u64,
+    vaddr: u64,
+    filesz: u64,
+}
+
+struct Section {
+    name: u32,
+    kind: u32,
+    offset: u64,
+    size: u64,
+    entsize: u64,
+}
+
+/// Reads fields from an ELF file according to its class and byte order.
+struct Reader<'a> {
+    data: &'a [u8],
+    class: ElfClass,
+    endianness: Endianness,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Result<Self, ElfError> {
+        if !data.starts_with(ELF_MAGIC) {
+            return Err(ElfError::BadMagic);
+        }
+        let class = match data.get(EI_CLASS) {
+            Some(&ELFCLASS32) => ElfClass::Elf32,
+            Some(&ELFCLASS64) => ElfClass::Elf64,
+            _ => return Err(ElfError::Malformed("invalid class")),
+        };
+        let endianness = match data.get(EI_DATA) {
+            Some(&ELFDATA2LSB) => Endianness::Little,
+            Some(&ELFDATA2MSB) => Endianness::Big,
+            _ => return Err(ElfError::Malformed("invalid data encoding")),
+        };
+        Ok(Self {
+            data,
+            class,
+            endianness,
+        })
+    }
+
+    fn slice(&self, offset: u64, len: u64) -> Result<&'a [u8], ElfError> {
+        usize::try_from(offset)
+            .ok()
+            .zip(usize::try_from(len).ok())
+            .and_then(|(offset, len)| self.data.get(offset..offset.checked_add(len)?))
+            .ok_or(ElfError::Truncated)
+    }
+
+    /// Returns the offset of entry `index` in a table of `entsize`-byte entries at `base`.
+    /// The offset is checked to be within the file, so that the fields of the entry can be
+    /// addressed without overflowing.
+    fn table_offset(&self, base: u64, index: u64, entsize: u64) -> Result<u64, ElfError> {
+        index
+            .checked_mul(entsize)
+            .and_then(|offset| base.checked_add(offset))
+            .filter(|&offset| offset <= self.data.len() as u64)
+            .ok_or(ElfError::Truncated)
+    }
+
+    fn bytes<const N: usize>(&self, offset: u64) -> Result<[u8; N], ElfError> {
+        Ok(self.slice(offset, N as u64)?.try_into().unwrap())
+    }
+
+    fn u8(&self, offset: u64) -> Result<u8, ElfError> {
+        Ok(self.bytes::<1>(offset)?[0])
+    }
+
+    fn u16(&self, offset: u64) -> Result<u16, ElfError> {
+        let bytes = self.bytes(offset)?;
+        Ok(match self.endianness {
+            Endianness::Little => u16::from_le_bytes(bytes),
+            Endianness::Big => u16::from_be_bytes(bytes),
+        })
+    }
+
+    fn u32(&self, offset: u64) -> Result<u32, ElfError> {
+        let bytes = self.bytes(offset)?;
+        Ok(match self.endianness {
+            Endianness::Little => u32::from_le_bytes(bytes),
+            Endianness::Big => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn u64(&self, offset: u64) -> Result<u64, ElfError> {
+        let bytes = self.bytes(offset)?;
+        Ok(match self.endianness {
+            Endianness::Little => u64::from_le_bytes(bytes),
+            Endianness::Big => u64::from_be_bytes(bytes),
+        })
+    }
+
+    /// Reads an address-sized field (`ElfN_Addr`, `ElfN_Off`, `ElfN_Xword`, ...).
+    fn word(&self, offset: u64) -> Result<u64, ElfError> {
+        match self.class {
+            ElfClass::Elf32 => self.u32(offset).map(u64::from),
+            ElfClass::Elf64 => self.u64(offset),
+        }
+    }
+
+    fn header(&self) -> Result<Header, ElfError> {
+        let machine = self.u16(18)?;
+        Ok(match self.class {
+            ElfClass::Elf32 => Header {
+                machine,
+                shstrndx: self.u16(50)?,
+                phoff: self.word(28)?,
+                shoff: self.word(32)?,
+                phentsize: self.u16(42)?,
+                phnum: self.u16(44)?,
+                shentsize: self.u16(46)?,
+                shnum: self.u16(48)?,
+            },
+            ElfClass::Elf64 => Header {
+                machine,
+                shstrndx: self.u16(62)?,
+                phoff: self.word(32)?,
+                shoff: self.word(40)?,
+                phentsize: self.u16(54)?,
+                phnum: self.u16(56)?,
+                shentsize: self.u16(58)?,
+                shnum: self.u16(60)?,
+            },
+        })
+    }
+
+    fn segments(&self, header: &Header) -> Result<Vec<Segment>, ElfError> {
+        (0..u64::from(header.phnum))
+            .map(|i| {
+                let base = self.table_offset(header.phoff, i, header.phentsize.into())?;
+                Ok(match self.class {
+                    ElfClass::Elf32 => Segment {
+                        kind: self.u32(base)?,
+                        offset: self.word(base + 4)?,
+                        vaddr: self.word(base + 8)?,
+                        filesz: self.word(base + 16)?,
+                    },
+                    ElfClass::Elf64 => Segment {
+                        kind: self.u32(base)?,
+                        offset: self.word(base + 8)?,
+                        vaddr: self.word(base + 16)?,
+                        filesz: self.word(base + 32)?,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    fn sections(&self, header: &Header) -> Result<Vec<Section>, ElfError> {
+        (0..u64::from(header.shnum))
+            .map(|i| {
+                let base = self.table_offset(header.shoff, i, header.shentsize.into())?;
+                Ok(match self.class {
+                    ElfClass::Elf32 => Section {
+                        name: self.u32(base)?,
+                        kind: self.u32(base + 4)?,
+                        offset: self.word(base + 16)?,
+                        size: self.word(base + 20)?,
+                        entsize: self.word(base + 36)?,
+                    },
+                    ElfClass::Elf64 => Section {
+                        name: self.u32(base)?,
+                        kind: self.u32(base + 4)?,
+                        offset: self.word(base + 24)?,
+                        size: self.word(base + 32)?,
+                        entsize: self.word(base + 56)?,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// Converts a virtual address to a file offset using the loadable segments.
+    fn addr_to_offset(&self, segments: &[Segment], addr: u64) -> Result<u64, ElfError> {
+        segments
+            .iter()
+            .filter(|seg| seg.kind == PT_LOAD)
+            .find_map(|seg| addr.checked_sub(seg.vaddr).filter(|&delta| delta < seg.filesz).map(|delta| (seg, delta)))
+            .ok_or(ElfError::Malformed("address is not in a loadable segment"))
+            .and_then(|(seg, delta)| {
+                seg.offset
+                    .checked_add(delta)
+                    .filter(|&offset| offset <= self.data.len() as u64)
+                    .ok_or(ElfError::Truncated)
+            })
+    }
+
+    fn dynamic_entries(&self, dynamic: &Segment) -> Result<Vec<(u64, u64)>, ElfError> {
+        let entsize = match self.class {
+            ElfClass::Elf32 => 8,
+            ElfClass::Elf64 => 16,
+        };
+        let mut entries = Vec::new();
+        for i in 0..dynamic.filesz / entsize {
+            let base = self.table_offset(dynamic.offset, i, entsize)?;
+            let tag = self.word(base)?;
+            if tag == DT_NULL {
+                break;
+            }
+            entries.push((tag, self.word(base + entsize / 2)?));
+        }
+        Ok(entries)
+    }
+
+    fn section_names(&self, header: &Header, sections: &[Section]) -> Result<Vec<String>, ElfError> {
+        let Some(shstrtab) = sections.get(header.shstrndx as usize) else {
+            return Ok(Vec::new());
+        };
+        let shstrtab = self.slice(shstrtab.offset, shstrtab.size)?;
+        sections
+            .iter()
+            .map(|sec| read_str(shstrtab, sec.name.into()))
+            .collect()
+    }
+
+    fn symbol(&self, base: u64, strtab: &[u8]) -> Result<ElfSymbol, ElfError> {
+        let (name, value, size, info, section_index) = match self.class {
+            ElfClass::Elf32 => (
+                self.u32(base)?,
+                self.word(base + 4)?,
+                self.word(base + 8)?,
+                self.u8(base + 12)?,
+                self.u16(base + 14)?,
+            ),
+            ElfClass::Elf64 => (
+                self.u32(base)?,
+                self.word(base + 8)?,
+                self.word(base + 16)?,
+                self.u8(base + 4)?,
+                self.u16(base + 6)?,
+            ),
+        };
+        Ok(ElfSymbol {
+            name: read_str(strtab, name.into())?,
+            version: None,
+            hidden: false,
+            value,
+            size,
+            info,
+            section_index,
+            section: None,
+        })
+    }
+
+    fn u32_array(&self, offset: u64, len: u64) -> Result<Vec<u32>, ElfError> {
+        (0..len).map(|i| self.u32(offset + i * 4)).collect()
+    }
+
+    fn sysv_hash_table(&self, offset: u64) -> Result<HashTable, ElfError> {
+        let nbucket = u64::from(self.u32(offset)?);
+        let nchain = u64::from(self.u32(offset + 4)?);
+        let buckets = self.u32_array(offset + 8, nbucket)?;
+        let chains = self.u32_array(offset + 8 + nbucket * 4, nchain)?;
+        Ok(HashTable::SysV { buckets, chains })
+    }
+
+    fn gnu_hash_table(&self, offset: u64) -> Result<HashTable, ElfError> {
+        let nbuckets = u64::from(self.u32(offset)?);
+        let symoffset = self.u32(offset + 4)?;
+        let bloom_size = u64::from(self.u32(offset + 8)?);
+        let word_size = match self.class {
+            ElfClass::Elf32 => 4,
+            ElfClass::Elf64 => 8,
+        };
+        let buckets_offset = offset + 16 + bloom_size * word_size;
+        let buckets = self.u32_array(buckets_offset, nbuckets)?;
+
+        // the table doesn't store its length, so find the end of the last chain
+        let chains_offset = buckets_offset + nbuckets * 4;
+        let mut chains = Vec::new();
+        if let Some(&last) = buckets.iter().max().filter(|&&last| last >= symoffset) {
+            for _ in symoffset..=last {
+                chains.push(self.u32(chains_offset + chains.len() as u64 * 4)?);
+            }
+            while chains.last().is_some_and(|chain| chain & 1 == 0) {
+                chains.push(self.u32(chains_offset + chains.len() as u64 * 4)?);
+            }
+        }
+
+        Ok(HashTable::Gnu {
+            symoffset,
+            buckets,
+            chains,
+        })
+    }
+
+    /// Reads `.gnu.version_d`, returning the index, flags and name of each version.
+    fn version_definitions(
+        &self,
+        mut offset: u64,
+        count: u64,
+        strtab: &[u8],
+    ) -> Result<Vec<(u16, u16, String)>, ElfError> {
+        let mut versions = Vec::new();
+        for _ in 0..count {
+            let flags = self.u16(offset + 2)?;
+            let index = self.u16(offset + 4)?;
+            let aux = u64::from(self.u32(offset + 12)?);
+            let next = u64::from(self.u32(offset + 16)?);
+            // the first auxiliary entry names the version itself, the rest name its parents
+            let name = read_str(strtab, self.u32(offset + aux)?.into())?;
+            versions.push((index, flags, name));
+            if next == 0 {
+                break;
+            }
+            offset += next;
+        }
+        Ok(versions)
+    }
+
+    /// Reads `.gnu.version_r`, returning each dependency and the index and name
+    /// of each version required from it.
+    #[allow(clippy::type_complexity)]
+    fn version_needs(
+        &self,
+        mut offset: u64,
+        count: u64,
+        strtab: &[u8],
+    ) -> Result<Vec<(String, Vec<(u16, String)>)>, ElfError> {
+        let mut needs = Vec::new();
+        for _ in 0..count {
+            let aux_count = self.u16(offset + 2)?;
+            let file = read_str(strtab, self.u32(offset + 4)?.into())?;
+            let next = u64::from(self.u32(offset + 12)?);
+
+            let mut versions = Vec::new();
+            let mut aux = offset + u64::from(self.u32(offset + 8)?);
+            for _ in 0..aux_count {
+                let index = self.u16(aux + 6)?;
+                let name = read_str(strtab, self.u32(aux + 8)?.into())?;
+                versions.push((index, name));
+                let aux_next = u64::from(self.u32(aux + 12)?);
+                if aux_next == 0 {
+                    break;
+                }
+                aux += aux_next;
+            }
+
+            needs.push((file, versions));
+            if next == 0 {
+                break;
+            }
+            offset += next;
+        }
+        Ok(needs)
+    }
+}
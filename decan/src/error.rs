@@ -1,4 +1,11 @@
-use std::{any, ffi::NulError, io};
+use std::{
+    any,
+    ffi::{CStr, NulError, OsStr},
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use crate::raw::{self, platform};
 
 /// Equivalent of [`std::ffi::NulError`] for wide strings.
 #[derive(Debug, thiserror::Error)]
@@ -20,12 +27,91 @@ impl WideNulError {
 
 
 
+/// How a library was located when it was loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoadMode {
+    /// The library was loaded from a filesystem path.
+    Path,
+    /// The library was loaded by name through the system search path.
+    Name,
+    /// The library was loaded from an in-memory image.
+    Memory,
+}
+
+impl fmt::Display for LoadMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadMode::Path => f.write_str("path"),
+            LoadMode::Name => f.write_str("name"),
+            LoadMode::Memory => f.write_str("memory"),
+        }
+    }
+}
+
+/// The cause of a [`LoadError::Loader`].
+///
+/// Classification is best-effort. Where the library is given as a path, its file is checked
+/// for existence, permissions and format first. Other causes, such as missing dependencies
+/// and undefined symbols, are recognized from the dynamic linker's message, which depends on
+/// the C library and may be translated, so unrecognized errors are reported as
+/// [`LoadErrorKind::Other`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LoadErrorKind {
+    /// The library could not be found.
+    NotFound,
+    /// The file is not a valid dynamic library.
+    InvalidFormat,
+    /// The library was built for a different architecture or word size.
+    ArchitectureMismatch,
+    /// One of the library's dependencies could not be found.
+    MissingDependency {
+        /// The name of the missing dependency.
+        name: String,
+    },
+    /// A symbol could not be resolved while relocating the library.
+    UndefinedSymbol {
+        /// The name of the undefined symbol.
+        name: String,
+    },
+    /// The library could not be opened due to insufficient permissions.
+    PermissionDenied,
+    /// Any other error.
+    Other,
+}
+
+impl fmt::Display for LoadErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadErrorKind::NotFound => f.write_str("library not found"),
+            LoadErrorKind::InvalidFormat => f.write_str("invalid library format"),
+            LoadErrorKind::ArchitectureMismatch => f.write_str("architecture mismatch"),
+            LoadErrorKind::MissingDependency { name } => write!(f, "missing dependency `{name}`"),
+            LoadErrorKind::UndefinedSymbol { name } => write!(f, "undefined symbol `{name}`"),
+            LoadErrorKind::PermissionDenied => f.write_str("permission denied"),
+            LoadErrorKind::Other => f.write_str("other error"),
+        }
+    }
+}
+
 /// An error that occurs when loading a dynamic library.
 #[derive(Debug, thiserror::Error)]
 pub enum LoadError {
     /// An error occurred in the operating system API while loading the library.
     #[error("OS error ({0})")]
     Os(#[source] io::Error),
+    /// The library could not be loaded.
+    #[error("Failed to load `{}` by {mode}: {kind} ({source})", path.display())]
+    Loader {
+        /// How the library was located.
+        mode: LoadMode,
+        /// The path or name that was passed to the dynamic linker.
+        path: PathBuf,
+        /// The classified cause of the error.
+        kind: LoadErrorKind,
+        /// The original error, including the dynamic linker's message.
+        #[source]
+        source: io::Error,
+    },
     /// An error occurred converting the path to a C string. This only occurs
     /// if the provided path contains null characters, which are invalid on most systems.
     #[error("Failed to create C string from path ({0})")]
@@ -36,6 +122,49 @@ pub enum LoadError {
     CWStr(#[source] WideNulError)
 }
 
+impl LoadError {
+    /// Attaches the load mode and attempted path to an error reported by the dynamic linker.
+    pub(crate) fn in_mode<P: AsRef<OsStr>>(self, mode: LoadMode, path: P) -> Self {
+        match self {
+            Self::Os(source) => {
+                let path = PathBuf::from(path.as_ref());
+                let kind = platform::classify_load_error(&path, &source);
+                Self::Loader { mode, path, kind, source }
+            }
+            other => other,
+        }
+    }
+
+    /// Returns the path or name of the library that failed to load, if known.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Loader { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Returns the classified cause of this error.
+    pub fn kind(&self) -> LoadErrorKind {
+        match self {
+            Self::Loader { kind, .. } => kind.clone(),
+            Self::Os(err) => match err.kind() {
+                io::ErrorKind::NotFound => LoadErrorKind::NotFound,
+                io::ErrorKind::PermissionDenied => LoadErrorKind::PermissionDenied,
+                _ => LoadErrorKind::Other,
+            },
+            Self::CStr(_) | Self::CWStr(_) => LoadErrorKind::Other,
+        }
+    }
+
+    /// Returns the original message reported by the operating system, if any.
+    pub fn message(&self) -> Option<String> {
+        match self {
+            Self::Os(source) | Self::Loader { source, .. } => Some(source.to_string()),
+            _ => None,
+        }
+    }
+}
+
 impl From<io::Error> for LoadError {
     fn from(value: io::Error) -> Self {
         Self::Os(value)
@@ -54,15 +183,62 @@ impl From<WideNulError> for LoadError {
     }
 }
 
+/// An error that occurs when reading an ELF file.
+#[derive(Debug, thiserror::Error)]
+pub enum ElfError {
+    /// An error occurred reading the file.
+    #[error("OS error ({0})")]
+    Os(#[source] io::Error),
+    /// The file does not start with the ELF magic number.
+    #[error("Not an ELF file")]
+    BadMagic,
+    /// A structure in the file extends past its end.
+    #[error("ELF file is truncated")]
+    Truncated,
+    /// The file's contents are inconsistent.
+    #[error("Malformed ELF file: {0}")]
+    Malformed(&'static str),
+}
+
+impl From<io::Error> for ElfError {
+    fn from(value: io::Error) -> Self {
+        Self::Os(value)
+    }
+}
+
+/// An error that occurs when closing a dynamic library.
+#[derive(Debug, thiserror::Error)]
+pub enum CloseError {
+    /// An error occurred in the operating system API while closing the library.
+    #[error("OS error ({0})")]
+    Os(#[source] io::Error),
+}
+
 /// Error that occurs when loading a [`Symbol`][crate::Symbol].
 #[derive(Debug, thiserror::Error)]
 pub enum SymbolError {
+    /// The library does not export a symbol with the given name.
+    #[error("Symbol `{name}` not found")]
+    NotFound {
+        /// The name of the missing symbol.
+        name: Box<str>,
+    },
     /// An error occurred in the OS's dynamic library loader.
     #[error("OS error ({0})")]
     Os(#[source] io::Error),
     /// A type expects a non-null value, but got a null value.
     #[error("Type {0} expects a non-null value")]
-    NullValue(&'static str)
+    NullValue(&'static str),
+    /// In strict resolution mode, the symbol was found in a different object
+    /// than the library it was requested from (e.g. one of its dependencies).
+    #[error("Symbol was resolved from `{}` instead of `{}`", actual.display(), expected.display())]
+    WrongProvenance {
+        /// The path of the library the symbol was requested from.
+        expected: PathBuf,
+        /// The path of the object the symbol was found in. This is empty
+        /// if the address doesn't belong to any loaded object.
+        actual: PathBuf,
+    },
 }
 
 impl SymbolError {
@@ -70,11 +246,43 @@ impl SymbolError {
         Self::NullValue(any::type_name::<T>())
     }
 
-    /// Combines this symbol error 
+    /// Converts an error returned by the platform's symbol lookup function,
+    /// separating missing symbols from other loader errors.
+    pub(crate) fn from_lookup(name: &CStr, error: io::Error) -> Self {
+        if platform::is_symbol_not_found(&error) {
+            Self::NotFound {
+                name: name.to_string_lossy().into(),
+            }
+        } else {
+            Self::Os(error)
+        }
+    }
+
+    /// Returns true if this error means the symbol is absent, i.e. it was not found
+    /// or its address is null. Optional symbols and groups treat these as `None`.
+    pub fn is_absent(&self) -> bool {
+        matches!(self, Self::NotFound { .. } | Self::NullValue(_))
+    }
+
+    /// Combines this symbol error with the name of the symbol that caused it.
     pub fn in_group<S: Into<Box<str>>>(self, name: S) -> SymbolGroupError {
+        let name = name.into();
+        SymbolGroupError {
+            path: name.clone(),
+            name,
+            inner: self,
+            suggestions: None,
+        }
+    }
+
+    /// Combines this symbol error with the path of the group member and
+    /// the name of the symbol that caused it.
+    pub fn in_field<P: Into<Box<str>>, S: Into<Box<str>>>(self, path: P, name: S) -> SymbolGroupError {
         SymbolGroupError {
+            path: path.into(),
             name: name.into(),
-            inner: self
+            inner: self,
+            suggestions: None,
         }
     }
 }
@@ -85,15 +293,38 @@ impl From<io::Error> for SymbolError {
     }
 }
 
-/// Error that occurs when loading a [`SymbolGroup`][crate::SymbolGroup].
+/// Error that occurs when loading a single member of a [`SymbolGroup`][crate::SymbolGroup].
 #[derive(Debug, thiserror::Error)]
-#[error("Error loading `{name}`: {inner}")]
+#[error("Error loading `{path}` (symbol `{name}`): {inner}{}", DidYouMean(self.suggestions()))]
 pub struct SymbolGroupError {
+    path: Box<str>,
     name: Box<str>,
-    #[source] inner: SymbolError
+    #[source] inner: SymbolError,
+    suggestions: Option<Vec<String>>,
+}
+
+/// Formats a list of suggestions as a "did you mean" hint.
+struct DidYouMean<'a>(&'a [String]);
+
+impl fmt::Display for DidYouMean<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some((first, rest)) = self.0.split_first() else {
+            return Ok(());
+        };
+        write!(f, " (did you mean `{first}`")?;
+        for name in rest {
+            write!(f, ", `{name}`")?;
+        }
+        f.write_str("?)")
+    }
 }
 
 impl SymbolGroupError {
+    /// The path of the group member that failed to load, e.g. `ext.gl_foo`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
     /// The name of the symbol that failed to load.
     pub fn name(&self) -> &str {
         &self.name
@@ -103,9 +334,209 @@ impl SymbolGroupError {
     pub fn inner(&self) -> &SymbolError {
         &self.inner
     }
+
+    /// Exported symbols with names similar to the missing symbol, closest first.
+    ///
+    /// This is only filled in for [`SymbolError::NotFound`] errors, on platforms where
+    /// the library's symbol table can be read (currently glibc). Suggestions are made when
+    /// a group is loaded into a [`Can`][crate::can::Can] or borrowed with
+    /// [`LibraryBorrowExt`][crate::borrow::LibraryBorrowExt], but not by
+    /// [`SymbolGroup::load`][crate::SymbolGroup::load] itself; use
+    /// [`SymbolGroupErrors::add_suggestions`] to add them to its errors.
+    pub fn suggestions(&self) -> &[String] {
+        self.suggestions.as_deref().unwrap_or_default()
+    }
+
+    fn nested(mut self, prefix: &str) -> Self {
+        self.path = format!("{prefix}.{}", self.path).into();
+        self
+    }
+}
+
+/// Error that occurs when loading a [`SymbolGroup`][crate::SymbolGroup].
+/// 
+/// Every member of the group is loaded before failing, so this contains
+/// one [`SymbolGroupError`] for each member that failed to load.
+#[derive(Debug, Default)]
+pub struct SymbolGroupErrors(Vec<SymbolGroupError>);
+
+impl SymbolGroupErrors {
+    /// Creates an empty set of errors.
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Adds an error to the set.
+    pub fn push(&mut self, error: SymbolGroupError) {
+        self.0.push(error);
+    }
+
+    /// Adds the errors from a subgroup to the set, prefixing their paths with `prefix`.
+    pub fn extend_nested(&mut self, prefix: &str, errors: SymbolGroupErrors) {
+        self.0.extend(errors.0.into_iter().map(|err| err.nested(prefix)));
+    }
+
+    /// Returns true if there are no errors in the set.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the number of errors in the set.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Iterates over the errors in the set.
+    pub fn iter(&self) -> std::slice::Iter<'_, SymbolGroupError> {
+        self.0.iter()
+    }
+
+    /// Returns the errors in the set as a slice.
+    pub fn errors(&self) -> &[SymbolGroupError] {
+        &self.0
+    }
+
+    /// Fills in [suggestions][SymbolGroupError::suggestions] for missing symbols,
+    /// using the names exported by the library.
+    ///
+    /// Pseudo-handles don't refer to a single library, so no suggestions are made for them.
+    /// # Safety
+    /// `handle` must be a valid library handle.
+    pub unsafe fn add_suggestions(&mut self, handle: raw::Handle) {
+        #[cfg(unix)]
+        if platform::is_pseudo_handle(handle) {
+            return;
+        }
+        let mut pending = self
+            .0
+            .iter_mut()
+            .filter(|err| err.suggestions.is_none())
+            .filter(|err| matches!(err.inner, SymbolError::NotFound { .. }))
+            .peekable();
+        if pending.peek().is_none() {
+            return;
+        }
+
+        let exports = exported_symbols(handle);
+        for err in pending {
+            if let SymbolError::NotFound { name } = &err.inner {
+                err.suggestions = Some(closest_names(name, &exports));
+            }
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+unsafe fn exported_symbols(handle: raw::Handle) -> Vec<String> {
+    platform::get_exported_symbols(handle)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|name| name.to_string_lossy().into_owned())
+        .collect()
+}
+
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+unsafe fn exported_symbols(_handle: raw::Handle) -> Vec<String> {
+    Vec::new()
+}
+
+/// Finds up to 3 of the `candidates` closest to `name`, ignoring differences in case
+/// and underscores (so `square_int` matches `squareInt`).
+fn closest_names(name: &str, candidates: &[String]) -> Vec<String> {
+    const MAX_SUGGESTIONS: usize = 3;
+
+    let normalize = |name: &str| -> Vec<char> {
+        name.chars()
+            .filter(|&c| c != '_')
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    };
+    let target: Vec<char> = name.chars().collect();
+    let target_normalized = normalize(name);
+    let max_distance = (target.len() / 3).max(2);
+
+    let mut matches: Vec<(usize, &String)> = candidates
+        .iter()
+        .filter(|candidate| candidate.as_str() != name)
+        .filter_map(|candidate| {
+            let exact = edit_distance(&target, &candidate.chars().collect::<Vec<_>>());
+            let normalized = edit_distance(&target_normalized, &normalize(candidate));
+            // prefer exact matches over ones that only match after normalization
+            let distance = exact.min(normalized + 1);
+            (distance <= max_distance).then_some((distance, candidate))
+        })
+        .collect();
+    matches.sort();
+    matches.dedup_by(|a, b| a.1 == b.1);
+    matches
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.clone())
+        .collect()
+}
+
+/// Computes the Levenshtein distance between two strings.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+impl fmt::Display for SymbolGroupErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.as_slice() {
+            [] => f.write_str("No errors"),
+            [error] => fmt::Display::fmt(error, f),
+            errors => {
+                write!(f, "{} symbols failed to load:", errors.len())?;
+                for error in errors {
+                    write!(f, "\n- {error}")?;
+                }
+                Ok(())
+            }
+        }
+    }
 }
 
-/// Either a [`LoadError`] or a [`SymbolGroupError`]. 
+impl std::error::Error for SymbolGroupErrors {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.first().map(|err| err as _)
+    }
+}
+
+impl From<SymbolGroupError> for SymbolGroupErrors {
+    fn from(value: SymbolGroupError) -> Self {
+        Self(vec![value])
+    }
+}
+
+impl IntoIterator for SymbolGroupErrors {
+    type Item = SymbolGroupError;
+    type IntoIter = std::vec::IntoIter<SymbolGroupError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a SymbolGroupErrors {
+    type Item = &'a SymbolGroupError;
+    type IntoIter = std::slice::Iter<'a, SymbolGroupError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// Either a [`LoadError`] or a [`SymbolGroupErrors`]. 
 /// 
 /// For now, this only occurs when calling [`can::Can::load`][crate::can::Can::load], 
 /// as this is the only call that both loads the library and symbols.
@@ -114,9 +545,9 @@ pub enum LoadOrSymbolGroupError {
     /// An OS error occurred when loading the library.
     #[error("Library loading failed: {0}")]
     Library(#[source] LoadError),
-    /// One of the symbols in the symbol group failed to load.
+    /// Some of the symbols in the symbol group failed to load.
     #[error("Symbol loading failed: {0}")]
-    Symbol(#[source] SymbolGroupError),
+    Symbol(#[source] SymbolGroupErrors),
 }
 
 impl From<LoadError> for LoadOrSymbolGroupError {
@@ -124,8 +555,55 @@ impl From<LoadError> for LoadOrSymbolGroupError {
         Self::Library(value)
     }
 }
-impl From<SymbolGroupError> for LoadOrSymbolGroupError {
-    fn from(value: SymbolGroupError) -> Self {
+impl From<SymbolGroupErrors> for LoadOrSymbolGroupError {
+    fn from(value: SymbolGroupErrors) -> Self {
+        Self::Symbol(value)
+    }
+}
+
+/// An error reloading a [`Can`][crate::can::Can] with [`Can::reload`][crate::can::Can::reload].
+///
+/// If reloading fails, the can is left unchanged and keeps using the previous library.
+#[derive(Debug, thiserror::Error)]
+pub enum ReloadError {
+    /// The can was not loaded from a file, so there is nothing to reload.
+    #[error("Can was not loaded from a file")]
+    NoSource,
+    /// The library could not be copied to a shadow path.
+    #[error("Failed to copy `{}` to `{}`: {source}", .from.display(), .to.display())]
+    ShadowCopy {
+        /// The library being reloaded.
+        from: PathBuf,
+        /// The shadow path it was being copied to.
+        to: PathBuf,
+        /// The underlying error.
+        #[source]
+        source: io::Error,
+    },
+    /// The library could not be read into memory.
+    #[error("Failed to read `{}`: {source}", .path.display())]
+    Read {
+        /// The library being loaded.
+        path: PathBuf,
+        /// The underlying error.
+        #[source]
+        source: io::Error,
+    },
+    /// The copy of the library could not be loaded.
+    #[error("Library loading failed: {0}")]
+    Library(#[source] LoadError),
+    /// Some of the symbols in the symbol group failed to load from the new library.
+    #[error("Symbol loading failed: {0}")]
+    Symbol(#[source] SymbolGroupErrors),
+}
+
+impl From<LoadError> for ReloadError {
+    fn from(value: LoadError) -> Self {
+        Self::Library(value)
+    }
+}
+impl From<SymbolGroupErrors> for ReloadError {
+    fn from(value: SymbolGroupErrors) -> Self {
         Self::Symbol(value)
     }
 }
\ No newline at end of file
@@ -0,0 +1,112 @@
+use std::sync::{Mutex, OnceLock};
+
+use crate::{
+    raw::{Library, LoadOptions},
+    LoadError,
+};
+
+/// A library embedded in the executable, which is loaded the first time it is used.
+///
+/// These are usually created with [`include_library!`][crate::include_library]. The
+/// image is loaded with [`Library::load_from_bytes_with`], so nothing is written to disk.
+/// Once loaded, the library stays loaded for the lifetime of the `LazyLibrary`, which for
+/// a `static` is the rest of the program.
+/// ```ignore
+/// use decan::{can::Can, include_library, LazyLibrary, SymbolGroup};
+///
+/// static BACKEND: LazyLibrary = include_library!("../native/libbackend.so");
+///
+/// #[derive(SymbolGroup)]
+/// struct Backend {
+///     backend_init: extern "C" fn() -> i32,
+/// }
+///
+/// let backend = unsafe { Can::<_, Backend>::with_handle(BACKEND.get()?)? };
+/// (backend.backend_init)();
+/// ```
+pub struct LazyLibrary {
+    image: &'static [u8],
+    options: LoadOptions,
+    library: OnceLock<Library>,
+    init: Mutex<()>,
+}
+
+impl LazyLibrary {
+    /// Creates a lazily loaded library from an ELF image, using the default [`LoadOptions`].
+    pub const fn new(image: &'static [u8]) -> Self {
+        Self::with_options(image, LoadOptions::new())
+    }
+
+    /// Creates a lazily loaded library from an ELF image, using the provided options.
+    pub const fn with_options(image: &'static [u8], options: LoadOptions) -> Self {
+        Self {
+            image,
+            options,
+            library: OnceLock::new(),
+            init: Mutex::new(()),
+        }
+    }
+
+    /// Returns the library, loading it if this is the first use.
+    ///
+    /// If loading fails, the error is returned and the next call tries again.
+    pub fn get(&self) -> Result<&Library, LoadError> {
+        if let Some(library) = self.library.get() {
+            return Ok(library);
+        }
+        // only one thread may load the image, or its initialization routines would run twice
+        let _guard = self.init.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(library) = self.library.get() {
+            return Ok(library);
+        }
+        let library = Library::load_from_bytes_with(self.image, &self.options)?;
+        Ok(self.library.get_or_init(|| library))
+    }
+
+    /// Returns the library if it has already been loaded, without loading it.
+    pub fn get_if_loaded(&self) -> Option<&Library> {
+        self.library.get()
+    }
+
+    /// Returns true if the library has been loaded.
+    pub fn is_loaded(&self) -> bool {
+        self.library.get().is_some()
+    }
+
+    /// The embedded ELF image.
+    pub fn image(&self) -> &'static [u8] {
+        self.image
+    }
+}
+
+impl std::fmt::Debug for LazyLibrary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyLibrary")
+            .field("image_len", &self.image.len())
+            .field("options", &self.options)
+            .field("loaded", &self.is_loaded())
+            .finish()
+    }
+}
+
+/// Embeds a shared library in the executable, producing a [`LazyLibrary`].
+///
+/// The path is resolved like [`include_bytes!`], i.e. relative to the current file. To
+/// embed a library built by a build script, such as a workspace `cdylib`, use
+/// `concat!(env!("OUT_DIR"), "/libname.so")`. Load options may be passed as a second argument:
+/// ```ignore
+/// # use decan::{include_library, raw::{Binding, LoadOptions}, LazyLibrary};
+/// static PLUGIN: LazyLibrary = include_library!(
+///     concat!(env!("OUT_DIR"), "/libplugin.so"),
+///     LoadOptions::new().binding(Binding::Now)
+/// );
+/// ```
+#[macro_export]
+macro_rules! include_library {
+    ($path:expr $(,)?) => {
+        $crate::LazyLibrary::new(::std::include_bytes!($path))
+    };
+    ($path:expr, $options:expr $(,)?) => {
+        $crate::LazyLibrary::with_options(::std::include_bytes!($path), $options)
+    };
+}
@@ -7,15 +7,40 @@
 //! This crate is inspired by [libloading](https://github.com/nagisa/rust_libloading) and 
 //! [dlopen](https://github.com/szymonwieloch/rust-dlopen).
 
+mod branded;
 mod error;
+#[cfg(target_os = "linux")]
+mod lazy;
+#[cfg(target_os = "linux")]
+mod plugin;
+#[cfg(target_os = "linux")]
+mod preflight;
+mod probe;
+#[cfg(target_os = "linux")]
+mod symbolizer;
+mod swap;
 mod traits;
+mod verify;
 
 pub mod raw;
 pub mod borrow;
 pub mod can;
+pub mod elf;
 pub mod util;
 
+pub use branded::*;
 pub use error::*;
+#[cfg(target_os = "linux")]
+pub use lazy::*;
+#[cfg(target_os = "linux")]
+pub use plugin::*;
+#[cfg(target_os = "linux")]
+pub use preflight::*;
+pub use probe::*;
+#[cfg(target_os = "linux")]
+pub use symbolizer::*;
+pub use swap::*;
 pub use traits::*;
+pub use verify::*;
 
 pub use decan_macros::SymbolGroup;
\ No newline at end of file
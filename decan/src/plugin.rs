@@ -0,0 +1,308 @@
+use std::{
+    collections::HashMap,
+    ffi::{c_int, CString, OsStr},
+    io,
+    os::{
+        fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
+        unix::ffi::OsStrExt,
+    },
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::{
+    can::Can,
+    raw::{Library, LoadOptions},
+    ReloadError, SymbolGroup,
+};
+
+/// A change to the plugins in a [`PluginDirectory`].
+#[derive(Debug)]
+pub enum PluginEvent {
+    /// A new plugin was loaded.
+    Added(PathBuf),
+    /// A plugin's file was replaced, and the new version was loaded. The previous
+    /// version is retired.
+    Modified(PathBuf),
+    /// A plugin's file was removed, and the plugin was retired.
+    Removed(PathBuf),
+    /// A retired plugin was no longer held by any caller, so it was unloaded.
+    Unloaded(PathBuf),
+    /// A plugin could not be loaded. If a previous version was loaded, it is kept.
+    Failed {
+        /// The path of the plugin.
+        path: PathBuf,
+        /// The reason it could not be loaded.
+        error: ReloadError,
+    },
+}
+
+impl PluginEvent {
+    /// The path of the plugin this event is about.
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Added(path)
+            | Self::Modified(path)
+            | Self::Removed(path)
+            | Self::Unloaded(path)
+            | Self::Failed { path, .. } => path,
+        }
+    }
+}
+
+/// A set of plugins loaded from the `.so` files in a directory, which is kept up to date
+/// as files are added, replaced and removed.
+///
+/// Changes are detected with `inotify(7)` and applied when [`PluginDirectory::poll`] or
+/// [`PluginDirectory::wait`] is called, which report them as [`PluginEvent`]s. Hidden files
+/// are ignored.
+///
+/// Each plugin is read into memory and loaded from there (see [`Library::load_from_bytes`]),
+/// so a plugin's file can be overwritten while it is loaded, and nothing is ever written to
+/// the directory, which may be read-only. Since plugins aren't loaded from the directory,
+/// their `$ORIGIN`-relative dependencies don't resolve.
+///
+/// Plugins are handed out as [`Arc`]s. When a plugin is removed or replaced, it is retired
+/// rather than unloaded, and is only unloaded by a later call to `poll` once no caller
+/// holds it anymore.
+/// ```no_run
+/// # use decan::{PluginDirectory, PluginEvent, SymbolGroup};
+/// #[derive(SymbolGroup)]
+/// struct Tool {
+///     tool_run: extern "C" fn(),
+/// }
+///
+/// let mut plugins = unsafe { PluginDirectory::<Tool>::open("plugins").unwrap() };
+/// loop {
+///     for event in plugins.wait(None).unwrap() {
+///         if let PluginEvent::Added(path) | PluginEvent::Modified(path) = event {
+///             plugins.get(&path).unwrap().symbols().tool_run.call();
+///         }
+///     }
+/// }
+/// ```
+pub struct PluginDirectory<G: SymbolGroup> {
+    path: PathBuf,
+    options: LoadOptions,
+    inotify: OwnedFd,
+    plugins: HashMap<PathBuf, Arc<Can<Library, G>>>,
+    retired: Vec<(PathBuf, Arc<Can<Library, G>>)>,
+    events: Vec<PluginEvent>,
+}
+
+impl<G: SymbolGroup> PluginDirectory<G> {
+    /// Starts watching a directory, loading the plugins already in it.
+    ///
+    /// The plugins already in the directory are reported as [`PluginEvent::Added`] by the
+    /// first call to [`PluginDirectory::poll`].
+    /// # Safety
+    /// The caller is responsible for ensuring that the symbols specified in `G` match
+    /// the exported symbols of every library placed in the directory, with the correct type.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::open_with(path, &LoadOptions::new())
+    }
+
+    /// Starts watching a directory, loading plugins using the provided options.
+    /// # Safety
+    /// See [`PluginDirectory::open`].
+    pub unsafe fn open_with<P: AsRef<Path>>(path: P, options: &LoadOptions) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        let fd = libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let inotify = OwnedFd::from_raw_fd(fd);
+
+        let c_path = CString::new(path.as_os_str().as_bytes())?;
+        // wait for files to be closed after writing, so that partially written files aren't loaded
+        let mask = libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_DELETE | libc::IN_MOVED_FROM;
+        if libc::inotify_add_watch(fd, c_path.as_ptr(), mask) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut this = Self {
+            path,
+            options: *options,
+            inotify,
+            plugins: HashMap::new(),
+            retired: Vec::new(),
+            events: Vec::new(),
+        };
+        // the watch is added first, so that files added while scanning aren't missed
+        this.rescan()?;
+        Ok(this)
+    }
+
+    /// The directory being watched.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the plugin loaded from a file, which may be given relative to the directory.
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<&Arc<Can<Library, G>>> {
+        self.plugins.get(&self.path.join(path))
+    }
+
+    /// Iterates over the loaded plugins and the paths they were loaded from.
+    pub fn plugins(&self) -> impl Iterator<Item = (&Path, &Arc<Can<Library, G>>)> {
+        self.plugins.iter().map(|(path, plugin)| (path.as_path(), plugin))
+    }
+
+    /// Returns the number of plugins that were removed or replaced, but are still held by a caller.
+    pub fn retired_count(&self) -> usize {
+        self.retired.len()
+    }
+
+    /// Applies any changes to the directory without blocking, and returns the resulting events.
+    ///
+    /// This also unloads retired plugins that are no longer held by any caller.
+    pub fn poll(&mut self) -> io::Result<Vec<PluginEvent>> {
+        let mut buffer = [0u8; 4096];
+        loop {
+            let len = unsafe {
+                libc::read(self.inotify.as_raw_fd(), buffer.as_mut_ptr().cast(), buffer.len())
+            };
+            if len < 0 {
+                let err = io::Error::last_os_error();
+                match err.kind() {
+                    io::ErrorKind::WouldBlock => break,
+                    io::ErrorKind::Interrupted => continue,
+                    _ => return Err(err),
+                }
+            }
+
+            let mut offset = 0;
+            while offset < len as usize {
+                let event: libc::inotify_event =
+                    unsafe { buffer.as_ptr().add(offset).cast::<libc::inotify_event>().read_unaligned() };
+                let name_start = offset + size_of::<libc::inotify_event>();
+                offset = name_start + event.len as usize;
+                // the name is padded with nul bytes
+                let name = buffer[name_start..offset].split(|&b| b == 0).next().unwrap_or_default();
+                self.handle_event(event.mask, OsStr::from_bytes(name))?;
+            }
+        }
+
+        self.retired.retain(|(path, plugin)| {
+            let unused = Arc::strong_count(plugin) == 1;
+            if unused {
+                self.events.push(PluginEvent::Unloaded(path.clone()));
+            }
+            !unused
+        });
+        Ok(std::mem::take(&mut self.events))
+    }
+
+    /// Waits until the directory changes or the timeout expires, then behaves like
+    /// [`PluginDirectory::poll`]. A timeout of `None` waits indefinitely.
+    ///
+    /// Only changes to the directory end the wait, so retired plugins are unloaded on the
+    /// next change at the latest.
+    pub fn wait(&mut self, timeout: Option<Duration>) -> io::Result<Vec<PluginEvent>> {
+        if self.events.is_empty() {
+            let timeout = timeout.map_or(-1, |timeout| timeout.as_millis().min(c_int::MAX as u128) as c_int);
+            let mut pollfd = libc::pollfd {
+                fd: self.inotify.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            if unsafe { libc::poll(&mut pollfd, 1, timeout) } < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+        }
+        self.poll()
+    }
+
+    fn handle_event(&mut self, mask: u32, name: &OsStr) -> io::Result<()> {
+        if mask & libc::IN_Q_OVERFLOW != 0 {
+            // events were lost, so compare the directory against the loaded plugins
+            return self.rescan();
+        }
+        if !is_plugin_name(name) {
+            return Ok(());
+        }
+        let path = self.path.join(name);
+        if mask & (libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO) != 0 {
+            self.load(path);
+        } else if mask & (libc::IN_DELETE | libc::IN_MOVED_FROM) != 0 {
+            self.remove(path);
+        }
+        Ok(())
+    }
+
+    /// Loads plugins that aren't loaded yet, and retires those whose files are gone.
+    fn rescan(&mut self) -> io::Result<()> {
+        let mut found = Vec::new();
+        for entry in std::fs::read_dir(&self.path)? {
+            let path = entry?.path();
+            if path.file_name().is_some_and(is_plugin_name) && path.is_file() {
+                found.push(path);
+            }
+        }
+
+        let removed: Vec<PathBuf> = self.plugins.keys().filter(|path| !found.contains(path)).cloned().collect();
+        for path in removed {
+            self.remove(path);
+        }
+        for path in found {
+            if !self.plugins.contains_key(&path) {
+                self.load(path);
+            }
+        }
+        Ok(())
+    }
+
+    fn load(&mut self, path: PathBuf) {
+        match unsafe { Can::load_in_memory(&path, &self.options) } {
+            Ok(plugin) => match self.plugins.insert(path.clone(), Arc::new(plugin)) {
+                Some(old) => {
+                    self.retired.push((path.clone(), old));
+                    self.events.push(PluginEvent::Modified(path));
+                }
+                None => self.events.push(PluginEvent::Added(path)),
+            },
+            Err(error) => self.events.push(PluginEvent::Failed { path, error }),
+        }
+    }
+
+    fn remove(&mut self, path: PathBuf) {
+        if let Some(old) = self.plugins.remove(&path) {
+            self.retired.push((path.clone(), old));
+            self.events.push(PluginEvent::Removed(path));
+        }
+    }
+}
+
+/// Returns true for the names of files that are loaded as plugins: visible `.so` files.
+fn is_plugin_name(name: &OsStr) -> bool {
+    let name = name.as_bytes();
+    !name.starts_with(b".") && name.ends_with(b".so")
+}
+
+impl<G: SymbolGroup> AsFd for PluginDirectory<G> {
+    /// The inotify file descriptor, which becomes readable when the directory changes.
+    /// This can be used to wait for changes in an event loop, before calling [`PluginDirectory::poll`].
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inotify.as_fd()
+    }
+}
+
+impl<G: SymbolGroup> AsRawFd for PluginDirectory<G> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inotify.as_raw_fd()
+    }
+}
+
+impl<G: SymbolGroup> std::fmt::Debug for PluginDirectory<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginDirectory")
+            .field("path", &self.path)
+            .field("plugins", &self.plugins.keys().collect::<Vec<_>>())
+            .field("retired", &self.retired.iter().map(|(path, _)| path).collect::<Vec<_>>())
+            .finish()
+    }
+}
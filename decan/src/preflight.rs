@@ -0,0 +1,370 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    env,
+    ffi::CString,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    elf::{ElfClass, ElfLibrary, Endianness, SymbolBinding},
+    raw::{self, Library},
+    ElfError,
+};
+
+/// The `e_machine` value for the current process, if known.
+const HOST_MACHINE: Option<u16> = if cfg!(target_arch = "x86") {
+    Some(3)
+} else if cfg!(target_arch = "x86_64") {
+    Some(62)
+} else if cfg!(target_arch = "arm") {
+    Some(40)
+} else if cfg!(target_arch = "aarch64") {
+    Some(183)
+} else if cfg!(target_arch = "riscv32") || cfg!(target_arch = "riscv64") {
+    Some(243)
+} else if cfg!(target_arch = "powerpc") {
+    Some(20)
+} else if cfg!(target_arch = "powerpc64") {
+    Some(21)
+} else if cfg!(target_arch = "s390x") {
+    Some(22)
+} else if cfg!(target_arch = "loongarch64") {
+    Some(258)
+} else {
+    None
+};
+
+/// Returns true if `library` has the word size, byte order and machine of the current process.
+pub(crate) fn matches_host(library: &ElfLibrary) -> bool {
+    library.class() == ElfClass::HOST
+        && library.endianness() == Endianness::HOST
+        && HOST_MACHINE.is_none_or(|host| host == library.machine())
+}
+
+/// Directories searched after `ld.so.conf`, like the dynamic linker's built-in defaults.
+#[cfg(target_pointer_width = "64")]
+const DEFAULT_DIRS: &[&str] = &["/lib64", "/usr/lib64", "/lib", "/usr/lib"];
+#[cfg(not(target_pointer_width = "64"))]
+const DEFAULT_DIRS: &[&str] = &["/lib", "/usr/lib"];
+
+/// A problem found by [`preflight`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreflightIssue {
+    /// The library's word size differs from the current process.
+    ClassMismatch {
+        /// The class of the current process.
+        expected: ElfClass,
+        /// The class of the library.
+        found: ElfClass,
+    },
+    /// The library's byte order differs from the current process.
+    EndiannessMismatch {
+        /// The byte order of the current process.
+        expected: Endianness,
+        /// The byte order of the library.
+        found: Endianness,
+    },
+    /// The library was built for a different machine than the current process.
+    MachineMismatch {
+        /// The `e_machine` value of the current process.
+        expected: u16,
+        /// The `e_machine` value of the library.
+        found: u16,
+    },
+    /// A dependency could not be found on the search path, and is not already loaded.
+    MissingDependency {
+        /// The name of the dependency (its `DT_NEEDED` entry).
+        name: String,
+        /// The library that depends on it.
+        required_by: PathBuf,
+    },
+    /// A symbol the library imports is not exported by any of its dependencies
+    /// or by the current process.
+    UndefinedSymbol {
+        /// The name of the symbol.
+        name: String,
+    },
+}
+
+impl fmt::Display for PreflightIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ClassMismatch { expected, found } => {
+                write!(f, "expected {expected:?} library, found {found:?}")
+            }
+            Self::EndiannessMismatch { expected, found } => {
+                write!(f, "expected {expected:?}-endian library, found {found:?}-endian")
+            }
+            Self::MachineMismatch { expected, found } => {
+                write!(f, "expected library for machine {expected}, found machine {found}")
+            }
+            Self::MissingDependency { name, required_by } => {
+                write!(f, "dependency `{name}` of `{}` not found", required_by.display())
+            }
+            Self::UndefinedSymbol { name } => write!(f, "undefined symbol `{name}`"),
+        }
+    }
+}
+
+/// The result of checking a library with [`preflight`].
+#[derive(Debug, Clone)]
+pub struct PreflightReport {
+    path: PathBuf,
+    dependencies: Vec<PathBuf>,
+    issues: Vec<PreflightIssue>,
+}
+
+impl PreflightReport {
+    /// The path of the library that was checked.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns true if no issues were found.
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// The paths of the dependencies that were found on disk, including indirect ones.
+    pub fn dependencies(&self) -> &[PathBuf] {
+        &self.dependencies
+    }
+
+    /// Returns the issues found in the library.
+    pub fn issues(&self) -> &[PreflightIssue] {
+        &self.issues
+    }
+
+    /// Iterates over the issues found in the library.
+    pub fn iter(&self) -> std::slice::Iter<'_, PreflightIssue> {
+        self.issues.iter()
+    }
+}
+
+impl IntoIterator for PreflightReport {
+    type Item = PreflightIssue;
+    type IntoIter = std::vec::IntoIter<PreflightIssue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.issues.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a PreflightReport {
+    type Item = &'a PreflightIssue;
+    type IntoIter = std::slice::Iter<'a, PreflightIssue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.issues.iter()
+    }
+}
+
+/// Checks whether a library can be loaded into the current process, without loading it.
+///
+/// Loading a library runs its initialization routines, so a broken library can crash
+/// the process before any error is reported. This reads the library's ELF file instead,
+/// and reports:
+/// - a word size, byte order or machine that differs from the current process,
+/// - dependencies that can't be found using the dynamic linker's search rules,
+/// - imported symbols that no dependency or already-loaded library exports.
+///
+/// The search follows `DT_RPATH`, `LD_LIBRARY_PATH`, `DT_RUNPATH`, the directories
+/// listed in `/etc/ld.so.conf` and the default library directories, in that order.
+/// Dependencies that are already loaded in the process are considered found.
+pub fn preflight<P: AsRef<Path>>(path: P) -> Result<PreflightReport, ElfError> {
+    let path = path.as_ref().to_path_buf();
+    let library = ElfLibrary::open(&path)?;
+
+    let mut report = PreflightReport {
+        path,
+        dependencies: Vec::new(),
+        issues: Vec::new(),
+    };
+
+    if library.class() != ElfClass::HOST {
+        report.issues.push(PreflightIssue::ClassMismatch {
+            expected: ElfClass::HOST,
+            found: library.class(),
+        });
+    }
+    if library.endianness() != Endianness::HOST {
+        report.issues.push(PreflightIssue::EndiannessMismatch {
+            expected: Endianness::HOST,
+            found: library.endianness(),
+        });
+    }
+    if let Some(host) = HOST_MACHINE.filter(|&host| host != library.machine()) {
+        report.issues.push(PreflightIssue::MachineMismatch {
+            expected: host,
+            found: library.machine(),
+        });
+    }
+    if !report.is_ok() {
+        // the library can't be loaded at all, so its dependencies don't matter
+        return Ok(report);
+    }
+
+    let search = SearchPath::new();
+    let mut exported = HashSet::new();
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([(report.path.clone(), library.clone())]);
+
+    while let Some((path, lib)) = queue.pop_front() {
+        for name in lib.needed() {
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            match search.find(name, &path, &lib) {
+                Some((dep_path, dep)) => {
+                    exported.extend(dep.exports().map(|sym| sym.name().to_owned()));
+                    report.dependencies.push(dep_path.clone());
+                    queue.push_back((dep_path, dep));
+                }
+                None if Library::is_loaded(name) => (),
+                None => report.issues.push(PreflightIssue::MissingDependency {
+                    name: name.clone(),
+                    required_by: path.clone(),
+                }),
+            }
+        }
+    }
+
+    let undefined = library
+        .imports()
+        .filter(|sym| sym.binding() == SymbolBinding::Global && !sym.name().is_empty())
+        .filter(|sym| !exported.contains(sym.name()) && !in_process(sym.name()));
+    for sym in undefined {
+        report.issues.push(PreflightIssue::UndefinedSymbol {
+            name: sym.name().to_owned(),
+        });
+    }
+
+    Ok(report)
+}
+
+/// Returns true if a symbol can be found in the global scope of the current process.
+fn in_process(name: &str) -> bool {
+    let Ok(name) = CString::new(name) else {
+        return false;
+    };
+    unsafe { raw::platform::get_symbol(raw::platform::DEFAULT_HANDLE, &name) }
+        .is_ok_and(|ptr| !ptr.is_null())
+}
+
+/// The directories the dynamic linker searches that don't depend on the requesting library.
+struct SearchPath {
+    ld_library_path: Vec<PathBuf>,
+    system: Vec<PathBuf>,
+}
+
+impl SearchPath {
+    fn new() -> Self {
+        let ld_library_path = env::var_os("LD_LIBRARY_PATH")
+            .map(|paths| env::split_paths(&paths).collect())
+            .unwrap_or_default();
+
+        let mut system = Vec::new();
+        read_ld_so_conf(Path::new("/etc/ld.so.conf"), &mut system, 0);
+        system.extend(DEFAULT_DIRS.iter().map(PathBuf::from));
+
+        Self {
+            ld_library_path,
+            system,
+        }
+    }
+
+    /// Finds a dependency of `requester` (located at `requester_path`).
+    fn find(
+        &self,
+        name: &str,
+        requester_path: &Path,
+        requester: &ElfLibrary,
+    ) -> Option<(PathBuf, ElfLibrary)> {
+        if name.contains('/') {
+            return open_compatible(Path::new(name), requester);
+        }
+
+        let origin = requester_path.parent().unwrap_or(Path::new("."));
+        let expand = |paths: Option<&str>| -> Vec<PathBuf> {
+            paths
+                .into_iter()
+                .flat_map(|paths| paths.split(':'))
+                .filter(|dir| !dir.is_empty())
+                .map(|dir| {
+                    let origin = origin.to_string_lossy();
+                    PathBuf::from(dir.replace("${ORIGIN}", &origin).replace("$ORIGIN", &origin))
+                })
+                .collect()
+        };
+
+        // DT_RPATH is ignored if DT_RUNPATH is present
+        let rpath = match requester.runpath() {
+            Some(_) => Vec::new(),
+            None => expand(requester.rpath()),
+        };
+        let runpath = expand(requester.runpath());
+
+        rpath
+            .iter()
+            .chain(&self.ld_library_path)
+            .chain(&runpath)
+            .chain(&self.system)
+            .find_map(|dir| open_compatible(&dir.join(name), requester))
+    }
+}
+
+/// Opens a library if it exists and could be loaded alongside `requester`.
+fn open_compatible(path: &Path, requester: &ElfLibrary) -> Option<(PathBuf, ElfLibrary)> {
+    let lib = ElfLibrary::open(path).ok()?;
+    let compatible = lib.class() == requester.class()
+        && lib.endianness() == requester.endianness()
+        && lib.machine() == requester.machine();
+    compatible.then(|| (path.to_path_buf(), lib))
+}
+
+/// Collects the directories listed in an `ld.so.conf` file, following `include` directives.
+fn read_ld_so_conf(path: &Path, dirs: &mut Vec<PathBuf>, depth: u32) {
+    const MAX_INCLUDE_DEPTH: u32 = 8;
+
+    let Ok(contents) = fs::read_to_string(path) else {
+        return;
+    };
+    for line in contents.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if let Some(pattern) = line.strip_prefix("include") {
+            if depth < MAX_INCLUDE_DEPTH {
+                let pattern = path.parent().unwrap_or(Path::new("/")).join(pattern.trim());
+                for include in glob_files(&pattern) {
+                    read_ld_so_conf(&include, dirs, depth + 1);
+                }
+            }
+        } else if !line.is_empty() && !line.starts_with("hwcap") {
+            dirs.push(PathBuf::from(line));
+        }
+    }
+}
+
+/// Expands a path whose file name may contain a single `*` wildcard.
+fn glob_files(pattern: &Path) -> Vec<PathBuf> {
+    let file_pattern = pattern.file_name().unwrap_or_default().to_string_lossy();
+    let Some((prefix, suffix)) = file_pattern.split_once('*') else {
+        return vec![pattern.to_path_buf()];
+    };
+    let Some(Ok(entries)) = pattern.parent().map(fs::read_dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            name.len() >= prefix.len() + suffix.len()
+                && name.starts_with(prefix)
+                && name.ends_with(suffix)
+        })
+        .collect();
+    files.sort();
+    files
+}
@@ -0,0 +1,174 @@
+use std::{
+    ffi::{c_void, CStr},
+    io,
+    ptr::NonNull,
+};
+
+use crate::{raw, SymbolError};
+
+/// The result of probing a single symbol.
+#[derive(Debug)]
+pub enum ProbeStatus {
+    /// The symbol was found at the given address.
+    Resolved(NonNull<c_void>),
+    /// The symbol was found, but its address is null.
+    Null,
+    /// The symbol could not be resolved.
+    Failed(SymbolError),
+}
+
+/// A single entry of a [`ProbeReport`].
+#[derive(Debug)]
+pub struct ProbeEntry {
+    path: Box<str>,
+    name: Box<str>,
+    status: ProbeStatus,
+}
+
+impl ProbeEntry {
+    /// Probes a symbol without converting it to its group member's type.
+    /// # Safety
+    /// `handle` must be a valid library handle.
+    pub unsafe fn probe<P: Into<Box<str>>, S: Into<Box<str>>>(
+        handle: raw::Handle,
+        path: P,
+        name: S,
+        cname: &CStr,
+    ) -> Self {
+        Self::from_result(path, name, cname, raw::platform::get_symbol(handle, cname))
+    }
+
+    /// Probes a specific version of a symbol. See [`ProbeEntry::probe`].
+    /// # Safety
+    /// `handle` must be a valid library handle.
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    pub unsafe fn probe_versioned<P: Into<Box<str>>, S: Into<Box<str>>>(
+        handle: raw::Handle,
+        path: P,
+        name: S,
+        cname: &CStr,
+        cversion: &CStr,
+    ) -> Self {
+        Self::from_result(
+            path,
+            name,
+            cname,
+            raw::platform::get_versioned_symbol(handle, cname, cversion),
+        )
+    }
+
+    fn from_result<P: Into<Box<str>>, S: Into<Box<str>>>(
+        path: P,
+        name: S,
+        cname: &CStr,
+        result: io::Result<*mut c_void>,
+    ) -> Self {
+        let status = match result {
+            Ok(ptr) => NonNull::new(ptr).map_or(ProbeStatus::Null, ProbeStatus::Resolved),
+            Err(err) => ProbeStatus::Failed(SymbolError::from_lookup(cname, err)),
+        };
+        Self {
+            path: path.into(),
+            name: name.into(),
+            status,
+        }
+    }
+
+    /// The path of the group member, e.g. `ext.gl_foo`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The name of the symbol.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The result of probing the symbol.
+    pub fn status(&self) -> &ProbeStatus {
+        &self.status
+    }
+
+    /// Returns true if the symbol was found, even if its address is null.
+    pub fn is_resolved(&self) -> bool {
+        !matches!(self.status, ProbeStatus::Failed(_))
+    }
+
+    /// Returns true if the symbol was found with a null address.
+    pub fn is_null(&self) -> bool {
+        matches!(self.status, ProbeStatus::Null)
+    }
+
+    /// Returns the error that occurred when resolving the symbol, if any.
+    pub fn error(&self) -> Option<&SymbolError> {
+        match &self.status {
+            ProbeStatus::Failed(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// A report of which symbols in a [`SymbolGroup`][crate::SymbolGroup] a library provides.
+///
+/// This is created by [`SymbolGroup::probe`][crate::SymbolGroup::probe], which never
+/// constructs the group itself.
+#[derive(Debug, Default)]
+pub struct ProbeReport(Vec<ProbeEntry>);
+
+impl ProbeReport {
+    /// Creates an empty report.
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Adds an entry to the report.
+    pub fn push(&mut self, entry: ProbeEntry) {
+        self.0.push(entry);
+    }
+
+    /// Adds the entries from a subgroup's report, prefixing their paths with `prefix`.
+    pub fn extend_nested(&mut self, prefix: &str, report: ProbeReport) {
+        self.0.extend(report.0.into_iter().map(|mut entry| {
+            entry.path = format!("{prefix}.{}", entry.path).into();
+            entry
+        }));
+    }
+
+    /// Returns true if every symbol was resolved.
+    pub fn all_resolved(&self) -> bool {
+        self.0.iter().all(ProbeEntry::is_resolved)
+    }
+
+    /// Looks up the entry for a group member by its path.
+    pub fn get(&self, path: &str) -> Option<&ProbeEntry> {
+        self.0.iter().find(|entry| &*entry.path == path)
+    }
+
+    /// Iterates over the entries in the report.
+    pub fn iter(&self) -> std::slice::Iter<'_, ProbeEntry> {
+        self.0.iter()
+    }
+
+    /// Returns the entries in the report as a slice.
+    pub fn entries(&self) -> &[ProbeEntry] {
+        &self.0
+    }
+}
+
+impl IntoIterator for ProbeReport {
+    type Item = ProbeEntry;
+    type IntoIter = std::vec::IntoIter<ProbeEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ProbeReport {
+    type Item = &'a ProbeEntry;
+    type IntoIter = std::slice::Iter<'a, ProbeEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
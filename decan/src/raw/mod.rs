@@ -5,7 +5,7 @@ pub mod unix;
 #[cfg(windows)]
 pub mod windows;
 
-use std::{ffi::{c_void, CString}, mem::ManuallyDrop, path::{Path, PathBuf}, ptr::NonNull};
+use std::{ffi::{c_void, CString, OsStr}, io, mem::ManuallyDrop, path::{Path, PathBuf}, ptr::NonNull};
 
 /// Alias to the current platform module.
 #[cfg(unix)]
@@ -14,11 +14,19 @@ pub use unix as platform;
 #[cfg(windows)]
 pub use windows as platform;
 
-use crate::{LibraryHandle, LoadError};
+use crate::{CloseError, LibraryHandle, LoadError, LoadMode};
 
 /// The platform library handle. This maps to `void*` on Unix-likes and `HMODULE` on Windows.
 pub type Handle = platform::Handle;
 
+pub use platform::LoadOptions;
+#[cfg(unix)]
+pub use platform::{Binding, Visibility};
+#[cfg(target_os = "linux")]
+pub use platform::ProgramHeader;
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+pub use platform::LinkMapInfo;
+
 /// A struct containing info about a pointer address.
 pub struct AddressInfo {
     pub lib_path: PathBuf,
@@ -28,16 +36,254 @@ pub struct AddressInfo {
     pub sym_addr: Option<NonNull<c_void>>,
 }
 
+/// A struct containing info about a module loaded in the process.
+#[cfg(target_os = "linux")]
+#[derive(Clone)]
+pub struct ModuleInfo {
+    /// The path the module was loaded from. This is empty for the main program.
+    pub path: PathBuf,
+    /// The address the module was loaded at, which program header addresses are relative to.
+    pub base_addr: *const c_void,
+    /// The module's program headers.
+    pub program_headers: Vec<ProgramHeader>,
+    /// The module's TLS module ID, if it has a TLS segment.
+    pub tls_module_id: Option<std::num::NonZeroUsize>,
+}
+
+#[cfg(target_os = "linux")]
+impl ModuleInfo {
+    /// Returns true if this entry describes the main program.
+    pub fn is_main_program(&self) -> bool {
+        self.path.as_os_str().is_empty()
+    }
+
+    /// Obtains a non-owning handle to this module without loading it again (`RTLD_NOLOAD`).
+    /// # Safety
+    /// The caller is responsible for ensuring that the module stays loaded for
+    /// the lifetime of the returned handle.
+    pub unsafe fn open_non_owning(&self) -> Result<ManuallyDrop<Library>, LoadError> {
+        let handle = if self.is_main_program() {
+            platform::open_self(&LoadOptions::new().noload(true))?
+        } else {
+            platform::load_library_with(self.path.as_os_str(), &LoadOptions::new().noload(true))
+                .map_err(|err| err.in_mode(LoadMode::Path, &self.path))?
+        };
+        // RTLD_NOLOAD still increments the reference count, which we don't own
+        platform::free_library(handle)?;
+        Ok(Library::wrap_raw(handle))
+    }
+}
+
+
+/// An isolated link-map namespace, created using `dlmopen(LM_ID_NEWLM, ...)`.
+/// 
+/// Libraries loaded into a namespace, along with their dependencies, do not share
+/// symbols with libraries in other namespaces. This allows loading conflicting copies
+/// of the same library, or even the same library twice with independent global state.
+/// 
+/// The underlying namespace is created when the first library is loaded into it,
+/// and is destroyed by the dynamic linker once every library in it is closed.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[derive(Debug, Default)]
+pub struct Namespace {
+    id: std::sync::Mutex<Option<platform::NamespaceId>>,
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+impl Namespace {
+    /// Creates a new namespace. No libraries are loaded until [`Library::load_in`] is called.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the ID of this namespace, if a library has been loaded into it.
+    pub fn id(&self) -> Option<platform::NamespaceId> {
+        *self.id.lock().unwrap()
+    }
+
+    fn load(&self, path: &OsStr, options: &LoadOptions) -> Result<Handle, LoadError> {
+        let mut id = self.id.lock().unwrap();
+        match *id {
+            Some(id) => unsafe { platform::load_library_in(id, path, options) },
+            None => {
+                let handle = unsafe { platform::load_library_in(platform::NEW_NAMESPACE, path, options)? };
+                match unsafe { platform::get_namespace_id(handle) } {
+                    Ok(new_id) => {
+                        *id = Some(new_id);
+                        Ok(handle)
+                    }
+                    Err(err) => {
+                        let _ = unsafe { platform::free_library(handle) };
+                        Err(err.into())
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// What a [`Library`] does when it is dropped without calling [`Library::close`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DropPolicy {
+    /// Close the library, panicking if closing fails. If the thread is
+    /// already panicking, the error is logged instead to avoid an abort.
+    #[default]
+    Panic,
+    /// Close the library, logging and ignoring any errors.
+    Log,
+    /// Never close the library, leaving it loaded for the rest of the process.
+    Leak,
+}
 
 /// An handle to an open library which frees it when dropped.
 /// 
 /// To prevent the handle from being dropped, use [`ManuallyDrop`].
-pub struct Library(Handle);
+/// To handle errors when closing the library, use [`Library::close`].
+pub struct Library {
+    handle: Handle,
+    drop_policy: DropPolicy,
+    pseudo: bool,
+}
 
 impl Library {
+    fn from_handle(handle: Handle) -> Self {
+        Self { handle, drop_policy: DropPolicy::default(), pseudo: false }
+    }
+
+    fn from_pseudo_handle(handle: Handle) -> Self {
+        Self { handle, drop_policy: DropPolicy::Leak, pseudo: true }
+    }
+
+    /// Obtains a handle to the main program.
+    /// 
+    /// On Unix-likes, symbols are looked up in the main program, its dependencies,
+    /// and any library loaded with [`Visibility::Global`].
+    pub fn this_process() -> Result<Self, LoadError> {
+        let handle = unsafe { platform::open_self(&LoadOptions::new())? };
+        #[cfg(unix)]
+        return Ok(Self::from_handle(handle));
+        #[cfg(windows)]
+        return Ok(Self::from_pseudo_handle(handle));
+    }
+
+    /// Obtains a pseudo-handle which searches the global scope of the process
+    /// in load order (`RTLD_DEFAULT`).
+    #[cfg(unix)]
+    pub fn global_scope() -> Self {
+        Self::from_pseudo_handle(platform::DEFAULT_HANDLE)
+    }
+
+    /// Obtains a pseudo-handle which searches the objects loaded after the
+    /// one performing the lookup (`RTLD_NEXT`). This is mainly useful for
+    /// interposing functions from another library.
+    /// 
+    /// Since lookups are performed by this crate, "the caller" is the object
+    /// this crate is linked into, which is usually the object using this handle.
+    #[cfg(unix)]
+    pub fn next_after_caller() -> Self {
+        Self::from_pseudo_handle(platform::NEXT_HANDLE)
+    }
+
     /// Loads a library from a path.
+    /// 
+    /// The path is canonicalized before loading, so it must exist on the filesystem.
+    /// To search for a library on the system search path, use [`Library::load_by_name`].
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
-        Ok(Self(unsafe { platform::load_library(path.as_ref().canonicalize()?.as_os_str())? }))
+        Self::load_with(path, &LoadOptions::new())
+    }
+
+    /// Loads a library from a path using the provided options.
+    pub fn load_with<P: AsRef<Path>>(path: P, options: &LoadOptions) -> Result<Self, LoadError> {
+        let path = path
+            .as_ref()
+            .canonicalize()
+            .map_err(|err| LoadError::from(err).in_mode(LoadMode::Path, path.as_ref()))?;
+        unsafe { platform::load_library_with(path.as_os_str(), options) }
+            .map(Self::from_handle)
+            .map_err(|err| err.in_mode(LoadMode::Path, &path))
+    }
+
+    /// Loads a library by name (e.g. `libz.so.1`).
+    /// 
+    /// The name is passed to the dynamic linker untouched, so it is located
+    /// using the platform's usual search rules (`LD_LIBRARY_PATH`, rpath, the
+    /// linker cache, etc. on Unix-likes).
+    pub fn load_by_name<S: AsRef<OsStr>>(name: S) -> Result<Self, LoadError> {
+        Self::load_by_name_with(name, &LoadOptions::new())
+    }
+
+    /// Loads a library by name using the provided options.
+    pub fn load_by_name_with<S: AsRef<OsStr>>(
+        name: S,
+        options: &LoadOptions,
+    ) -> Result<Self, LoadError> {
+        unsafe { platform::load_library_with(name.as_ref(), options) }
+            .map(Self::from_handle)
+            .map_err(|err| err.in_mode(LoadMode::Name, name))
+    }
+
+    /// Loads a library from an ELF image held in memory, without writing it to disk.
+    /// 
+    /// The image is copied into a sealed anonymous file (see `memfd_create(2)`), which
+    /// the dynamic linker loads through `/proc/self/fd`. Each call loads a separate
+    /// copy of the library, even if the same image was loaded before.
+    #[cfg(target_os = "linux")]
+    pub fn load_from_bytes(image: &[u8]) -> Result<Self, LoadError> {
+        Self::load_from_bytes_with(image, &LoadOptions::new())
+    }
+
+    /// Loads a library from an ELF image held in memory using the provided options.
+    #[cfg(target_os = "linux")]
+    pub fn load_from_bytes_with(image: &[u8], options: &LoadOptions) -> Result<Self, LoadError> {
+        unsafe { platform::load_library_from_bytes(image, options) }.map(Self::from_handle)
+    }
+
+    /// Loads a library from a path into an isolated [`Namespace`].
+    /// 
+    /// Libraries in a namespace other than the main one cannot be loaded with
+    /// [`Visibility::Global`], so this uses [`Visibility::Local`].
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    pub fn load_in<P: AsRef<Path>>(namespace: &Namespace, path: P) -> Result<Self, LoadError> {
+        Self::load_in_with(namespace, path, &LoadOptions::new().visibility(Visibility::Local))
+    }
+
+    /// Loads a library from a path into an isolated [`Namespace`] using the provided options.
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    pub fn load_in_with<P: AsRef<Path>>(
+        namespace: &Namespace,
+        path: P,
+        options: &LoadOptions,
+    ) -> Result<Self, LoadError> {
+        let path = path
+            .as_ref()
+            .canonicalize()
+            .map_err(|err| LoadError::from(err).in_mode(LoadMode::Path, path.as_ref()))?;
+        namespace
+            .load(path.as_os_str(), options)
+            .map(Self::from_handle)
+            .map_err(|err| err.in_mode(LoadMode::Path, &path))
+    }
+
+    /// Obtains a handle to a library by name only if it is already loaded,
+    /// without ever loading a fresh copy.
+    /// 
+    /// The name is interpreted the same way as in [`Library::load_by_name`]. If the
+    /// library is loaded, its reference count is incremented, so it stays loaded
+    /// until the returned handle is dropped.
+    ///
+    /// Returns an error if the name can't be passed to the dynamic linker, e.g. because
+    /// it contains a nul character.
+    pub fn get_if_loaded<S: AsRef<OsStr>>(name: S) -> Result<Option<Self>, LoadError> {
+        unsafe { platform::get_loaded_library(name.as_ref()) }
+            .map(|handle| handle.map(Self::from_handle))
+            .map_err(|err| err.in_mode(LoadMode::Name, name))
+    }
+
+    /// Returns true if a library with this name is already loaded.
+    /// See [`Library::get_if_loaded`]. Names that can't be passed to the dynamic
+    /// linker are never loaded, so they return false.
+    pub fn is_loaded<S: AsRef<OsStr>>(name: S) -> bool {
+        matches!(Self::get_if_loaded(name), Ok(Some(_)))
     }
 
     /// Wraps a raw library handle in a non-owning reference.
@@ -45,7 +291,91 @@ impl Library {
     /// The caller is responsible for ensuring that the provided handle
     /// is valid for the lifetime of this object.
     pub unsafe fn wrap_raw(handle: Handle) -> ManuallyDrop<Self> {
-        ManuallyDrop::new(Self(handle))
+        ManuallyDrop::new(Self::from_handle(handle))
+    }
+
+    /// Returns the policy used when this library is dropped.
+    pub fn drop_policy(&self) -> DropPolicy {
+        self.drop_policy
+    }
+
+    /// Sets the policy used when this library is dropped.
+    /// 
+    /// This has no effect on pseudo-handles, which are never closed.
+    pub fn set_drop_policy(&mut self, policy: DropPolicy) {
+        if !self.pseudo {
+            self.drop_policy = policy;
+        }
+    }
+
+    /// Sets the policy used when this library is dropped.
+    /// 
+    /// This has no effect on pseudo-handles, which are never closed.
+    pub fn with_drop_policy(mut self, policy: DropPolicy) -> Self {
+        self.set_drop_policy(policy);
+        self
+    }
+
+    /// Reads this library's link map entry and dynamic section.
+    /// 
+    /// Pseudo-handles have no link map entry, so this fails for them.
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    pub fn link_map_info(&self) -> io::Result<LinkMapInfo> {
+        if self.pseudo {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "pseudo-handles do not have a link map",
+            ));
+        }
+        unsafe { platform::get_link_map_info(self.handle) }
+    }
+
+    /// Returns the path this library was loaded from.
+    /// 
+    /// For the main program, this is the path of the current executable.
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    pub fn path(&self) -> io::Result<PathBuf> {
+        let info = self.link_map_info()?;
+        if info.path.as_os_str().is_empty() {
+            std::env::current_exe()
+        } else {
+            Ok(info.path)
+        }
+    }
+
+    /// Returns this library's `DT_SONAME`, if it has one.
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    pub fn soname(&self) -> io::Result<Option<CString>> {
+        Ok(self.link_map_info()?.soname)
+    }
+
+    /// Returns the address this library was loaded at, relative to the addresses in its ELF file.
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    pub fn base_address(&self) -> io::Result<*const c_void> {
+        Ok(self.link_map_info()?.base_addr)
+    }
+
+    /// Returns the names of the libraries this library depends on (its `DT_NEEDED` entries).
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    pub fn dependencies(&self) -> io::Result<Vec<CString>> {
+        Ok(self.link_map_info()?.needed)
+    }
+
+    /// Returns true if this is a pseudo-handle, which is never closed.
+    pub fn is_pseudo(&self) -> bool {
+        self.pseudo
+    }
+
+    /// Closes the library, returning any error reported by the dynamic linker.
+    /// 
+    /// The library is closed regardless of its [`DropPolicy`], unless it is a
+    /// [pseudo-handle][Library::is_pseudo].
+    pub fn close(self) -> Result<(), CloseError> {
+        let this = ManuallyDrop::new(self);
+        if this.pseudo {
+            return Ok(());
+        }
+        unsafe { platform::free_library(this.handle) }.map_err(CloseError::Os)
     }
 }
 
@@ -54,23 +384,39 @@ unsafe impl Sync for Library {}
 
 impl Drop for Library {
     fn drop(&mut self) {
-        platform::free_library(self.0);
+        if self.drop_policy == DropPolicy::Leak {
+            return;
+        }
+        if let Err(err) = unsafe { platform::free_library(self.handle) } {
+            if self.drop_policy == DropPolicy::Panic && !std::thread::panicking() {
+                panic!("failed to close library: {err}");
+            }
+            log::error!("failed to close library: {err}");
+        }
     }
 }
 
 impl LibraryHandle for Library {
     unsafe fn as_raw(&self) -> self::Handle {
-        self.0
+        self.handle
     }
 }
 
 impl LibraryHandle for ManuallyDrop<Library> {
     unsafe fn as_raw(&self) -> self::Handle {
-        self.0
+        self.handle
     }
 }
 
+/// Lists the modules currently loaded in the process, in load order.
+#[cfg(target_os = "linux")]
+pub fn loaded_modules() -> std::vec::IntoIter<ModuleInfo> {
+    platform::loaded_modules().into_iter()
+}
+
 /// Obtains dynamic-linker information from a memory address.
+/// # Safety
+/// The library containing `ptr` must not be unloaded during this call.
 #[inline(always)]
 pub unsafe fn get_address_info(ptr: *const c_void) -> Option<AddressInfo> {
     platform::get_address_info(ptr)
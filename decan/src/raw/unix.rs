@@ -2,20 +2,26 @@
 //!
 //! This uses the POSIX `dlopen()`/`dlsym()`/`dlclose()` APIs.
 
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+use std::sync::Mutex;
 use std::{
     ffi::{c_int, c_void, CStr, CString, OsStr},
     io,
     os::unix::ffi::OsStrExt,
-    path::{Path, PathBuf},
+    path::Path,
     ptr::{null, null_mut, NonNull},
-    sync::Mutex,
 };
 
-use libc::{dlclose, dlerror, dlsym, RTLD_GLOBAL, RTLD_LAZY};
+use libc::{
+    dlclose, dlerror, dlsym, RTLD_GLOBAL, RTLD_LAZY, RTLD_LOCAL, RTLD_NODELETE, RTLD_NOLOAD,
+    RTLD_NOW,
+};
 
-use crate::LoadError;
+use crate::{LoadError, LoadErrorKind, SymbolError};
 
 use super::AddressInfo;
+#[cfg(target_os = "linux")]
+use super::ModuleInfo;
 
 /// The Unix dynamic library handle, `*mut c_void`.
 pub type Handle = *mut c_void;
@@ -39,7 +45,112 @@ where
     f()
 }
 
-const DEFAULT_FLAGS: c_int = RTLD_GLOBAL | RTLD_LAZY;
+/// When relocations in a library are performed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    /// Resolve function symbols only when they are first called (`RTLD_LAZY`).
+    Lazy,
+    /// Resolve all symbols before `dlopen()` returns (`RTLD_NOW`).
+    Now,
+}
+
+/// Whether a library's symbols are available to subsequently loaded libraries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Visibility {
+    /// Symbols are added to the global namespace (`RTLD_GLOBAL`).
+    Global,
+    /// Symbols are kept out of the global namespace (`RTLD_LOCAL`).
+    Local,
+}
+
+/// Flags passed to `dlopen()` when loading a library.
+///
+/// The default options are `RTLD_GLOBAL | RTLD_LAZY`, which is what
+/// [`load_library`] uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LoadOptions {
+    binding: Binding,
+    visibility: Visibility,
+    nodelete: bool,
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    deepbind: bool,
+    noload: bool,
+}
+
+impl LoadOptions {
+    /// Creates the default set of options (`RTLD_GLOBAL | RTLD_LAZY`).
+    pub const fn new() -> Self {
+        Self {
+            binding: Binding::Lazy,
+            visibility: Visibility::Global,
+            nodelete: false,
+            #[cfg(all(target_os = "linux", target_env = "gnu"))]
+            deepbind: false,
+            noload: false,
+        }
+    }
+
+    /// Sets when relocations are performed.
+    pub const fn binding(mut self, binding: Binding) -> Self {
+        self.binding = binding;
+        self
+    }
+
+    /// Sets whether the library's symbols are made globally available.
+    pub const fn visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// Sets `RTLD_NODELETE`, which keeps the library mapped after it is closed.
+    pub const fn nodelete(mut self, nodelete: bool) -> Self {
+        self.nodelete = nodelete;
+        self
+    }
+
+    /// Sets `RTLD_DEEPBIND`, which makes the library prefer its own symbols
+    /// over global symbols with the same name.
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    pub const fn deepbind(mut self, deepbind: bool) -> Self {
+        self.deepbind = deepbind;
+        self
+    }
+
+    /// Sets `RTLD_NOLOAD`, which only succeeds if the library is already loaded.
+    pub const fn noload(mut self, noload: bool) -> Self {
+        self.noload = noload;
+        self
+    }
+
+    /// Returns the flags that will be passed to `dlopen()`.
+    pub const fn flags(&self) -> c_int {
+        let mut flags = match self.binding {
+            Binding::Lazy => RTLD_LAZY,
+            Binding::Now => RTLD_NOW,
+        };
+        flags |= match self.visibility {
+            Visibility::Global => RTLD_GLOBAL,
+            Visibility::Local => RTLD_LOCAL,
+        };
+        if self.nodelete {
+            flags |= RTLD_NODELETE;
+        }
+        #[cfg(all(target_os = "linux", target_env = "gnu"))]
+        if self.deepbind {
+            flags |= libc::RTLD_DEEPBIND;
+        }
+        if self.noload {
+            flags |= RTLD_NOLOAD;
+        }
+        flags
+    }
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 /// Loads a library from a path.
 ///
@@ -49,22 +160,314 @@ const DEFAULT_FLAGS: c_int = RTLD_GLOBAL | RTLD_LAZY;
 /// dlopen(path, RTLD_GLOBAL | RTLD_LAZY);
 /// ```
 /// with additional error checking.
-pub unsafe fn load_library(path: &OsStr) -> Result<*mut c_void, LoadError> {
+/// # Safety
+/// Loading a library runs its initialization routines, which may do anything.
+pub unsafe fn load_library(path: &OsStr) -> Result<Handle, LoadError> {
+    load_library_with(path, &LoadOptions::new())
+}
+
+/// Loads a library from a path using the provided options.
+///
+/// This is equivalent to:
+/// ```c
+/// #include <dlfcn.h>
+/// dlopen(path, flags);
+/// ```
+/// with additional error checking.
+/// # Safety
+/// Loading a library runs its initialization routines, which may do anything.
+pub unsafe fn load_library_with(path: &OsStr, options: &LoadOptions) -> Result<Handle, LoadError> {
+    let path = CString::new(path.as_bytes())?;
+    dlopen_checked(path.as_ptr(), options.flags())
+}
+
+/// Loads a library from an in-memory image.
+///
+/// The image is written to an anonymous file created with `memfd_create`, which is
+/// sealed against further modification and then loaded through `/proc/self/fd/N`.
+/// The file is closed once the library is loaded, so the library's path refers to a
+/// file descriptor that no longer exists.
+///
+/// Unlike the other loading functions, the returned error already records the load
+/// mode, and the path that was passed to the dynamic linker, or `memfd:decan-library`
+/// if the anonymous file couldn't be prepared.
+/// # Safety
+/// Loading a library runs its initialization routines, which may do anything.
+#[cfg(target_os = "linux")]
+pub unsafe fn load_library_from_bytes(
+    image: &[u8],
+    options: &LoadOptions,
+) -> Result<Handle, LoadError> {
+    use std::{
+        fs::File,
+        io::Write,
+        os::fd::{AsRawFd, FromRawFd},
+    };
+
+    // errors before the library is passed to the dynamic linker are reported against
+    // the anonymous file, named the way it appears in `/proc/self/fd`
+    let memfd_error = |err: io::Error| LoadError::from(err).in_mode(crate::LoadMode::Memory, "memfd:decan-library");
+
+    let fd = libc::memfd_create(
+        c"decan-library".as_ptr(),
+        libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING,
+    );
+    if fd < 0 {
+        return Err(memfd_error(io::Error::last_os_error()));
+    }
+    let mut file = File::from_raw_fd(fd);
+    file.write_all(image).map_err(memfd_error)?;
+
+    let seals = libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_WRITE | libc::F_SEAL_SEAL;
+    if libc::fcntl(fd, libc::F_ADD_SEALS, seals) < 0 {
+        return Err(memfd_error(io::Error::last_os_error()));
+    }
+
+    // The dynamic linker recognizes loaded libraries by the path they were loaded from, so
+    // if a library loaded from a since-closed descriptor with the same number is still
+    // loaded, it would be returned instead. Duplicate the descriptor until the path is unused.
+    let mut reserved = Vec::new();
+    let mut path = format!("/proc/self/fd/{fd}");
+    while loaded_modules().iter().any(|module| module.path == Path::new(&path)) {
+        let duplicate = file.try_clone().map_err(memfd_error)?;
+        path = format!("/proc/self/fd/{}", duplicate.as_raw_fd());
+        reserved.push(std::mem::replace(&mut file, duplicate));
+    }
+
+    load_library_with(OsStr::new(&path), options)
+        .map_err(|err| err.in_mode(crate::LoadMode::Memory, &path))
+}
+
+/// Identifies a link-map namespace.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+pub type NamespaceId = libc::Lmid_t;
+
+/// Namespace ID which creates a new namespace when passed to [`load_library_in`].
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+pub const NEW_NAMESPACE: NamespaceId = libc::LM_ID_NEWLM;
+
+/// Loads a library from a path into a link-map namespace.
+///
+/// This is equivalent to:
+/// ```c
+/// #define _GNU_SOURCE
+/// #include <dlfcn.h>
+/// dlmopen(namespace, path, flags);
+/// ```
+/// with additional error checking.
+/// # Safety
+/// Loading a library runs its initialization routines, which may do anything.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+pub unsafe fn load_library_in(
+    namespace: NamespaceId,
+    path: &OsStr,
+    options: &LoadOptions,
+) -> Result<Handle, LoadError> {
     let path = CString::new(path.as_bytes())?;
 
     with_dlerror_lock(|| {
-        let handle = libc::dlopen(path.as_ptr(), DEFAULT_FLAGS);
+        let handle = libc::dlmopen(namespace, path.as_ptr(), options.flags());
+        if handle.is_null() {
+            let msg = CStr::from_ptr(dlerror());
+            return Err(io::Error::other(msg.to_string_lossy().into_owned()).into());
+        }
+
+        Ok(handle)
+    })
+}
+
+/// Gets the link-map namespace a library was loaded into.
+///
+/// This is equivalent to:
+/// ```c
+/// #define _GNU_SOURCE
+/// #include <dlfcn.h>
+/// Lmid_t namespace;
+/// dlinfo(handle, RTLD_DI_LMID, &namespace);
+/// ```
+/// with additional error checking.
+/// # Safety
+/// `handle` must be a valid library handle, and not a pseudo-handle.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+pub unsafe fn get_namespace_id(handle: Handle) -> io::Result<NamespaceId> {
+    with_dlerror_lock(|| {
+        let mut namespace: NamespaceId = 0;
+        if libc::dlinfo(handle, libc::RTLD_DI_LMID, &mut namespace as *mut NamespaceId as *mut c_void) != 0 {
+            let msg = dlerror();
+            return Err(io::Error::other(
+                CStr::from_ptr(msg).to_string_lossy().into_owned(),
+            ));
+        }
+        Ok(namespace)
+    })
+}
+
+/// Opens a handle to the main program.
+///
+/// This is equivalent to:
+/// ```c
+/// #include <dlfcn.h>
+/// dlopen(NULL, flags);
+/// ```
+/// with additional error checking.
+/// # Safety
+/// This function is always safe to call, but is marked `unsafe` for consistency with [`load_library`].
+pub unsafe fn open_self(options: &LoadOptions) -> Result<Handle, LoadError> {
+    dlopen_checked(null(), options.flags())
+}
+
+unsafe fn dlopen_checked(path: *const std::ffi::c_char, flags: c_int) -> Result<Handle, LoadError> {
+    with_dlerror_lock(|| {
+        let handle = libc::dlopen(path, flags);
         if handle.is_null() {
             let msg = CStr::from_ptr(dlerror());
-            return Err(
-                io::Error::new(io::ErrorKind::Other, msg.to_string_lossy().into_owned()).into(),
-            );
+            return Err(io::Error::other(msg.to_string_lossy().into_owned()).into());
         }
 
         Ok(handle)
     })
 }
 
+/// Obtains a handle to a library only if it is already loaded.
+///
+/// This is equivalent to:
+/// ```c
+/// #include <dlfcn.h>
+/// dlopen(name, RTLD_LAZY | RTLD_NOLOAD);
+/// ```
+/// If a handle is returned, the library's reference count has been incremented.
+/// # Safety
+/// This function is always safe to call, as it never runs initialization routines.
+/// It is marked `unsafe` for consistency with [`load_library`].
+pub unsafe fn get_loaded_library(name: &OsStr) -> Result<Option<Handle>, LoadError> {
+    let name = CString::new(name.as_bytes())?;
+    with_dlerror_lock(|| {
+        let handle = libc::dlopen(name.as_ptr(), RTLD_LAZY | RTLD_NOLOAD);
+        if handle.is_null() {
+            // a library that isn't loaded still sets an error, which would otherwise be
+            // reported by the next unrelated call to `dlerror()`
+            dlerror();
+            return Ok(None);
+        }
+        Ok(Some(handle))
+    })
+}
+
+/// Pseudo-handle which searches the global scope of the process (`RTLD_DEFAULT`).
+/// 
+/// This handle must never be passed to [`free_library`].
+pub const DEFAULT_HANDLE: Handle = libc::RTLD_DEFAULT;
+
+/// Pseudo-handle which searches the objects loaded after the one calling
+/// the lookup function (`RTLD_NEXT`).
+/// 
+/// This handle must never be passed to [`free_library`].
+pub const NEXT_HANDLE: Handle = libc::RTLD_NEXT;
+
+/// Returns true if `handle` is [`DEFAULT_HANDLE`] or [`NEXT_HANDLE`], which don't refer
+/// to a single library.
+pub fn is_pseudo_handle(handle: Handle) -> bool {
+    handle == DEFAULT_HANDLE || handle == NEXT_HANDLE
+}
+
+/// Classifies an error returned by [`load_library`].
+///
+/// When `path` names a file, the file itself is inspected first. Otherwise, and for
+/// errors in the library's dependencies, this falls back to the dynamic linker's message,
+/// recognizing the messages produced by glibc, musl and the macOS dynamic linker.
+pub(crate) fn classify_load_error(path: &Path, error: &io::Error) -> LoadErrorKind {
+    match error.kind() {
+        io::ErrorKind::NotFound => return LoadErrorKind::NotFound,
+        io::ErrorKind::PermissionDenied => return LoadErrorKind::PermissionDenied,
+        _ => (),
+    }
+    if let Some(kind) = classify_library_file(path) {
+        return kind;
+    }
+
+    let msg = error.to_string();
+    let name_after = |prefix: &str| {
+        msg.split_once(prefix).map(|(_, rest)| {
+            rest.split([',', ' ', '\n', '(']).next().unwrap_or_default().to_owned()
+        })
+    };
+
+    if let Some(name) = name_after("undefined symbol: ").or_else(|| name_after("Symbol not found: ")) {
+        LoadErrorKind::UndefinedSymbol { name }
+    } else if msg.contains("not defined in file") {
+        // glibc: "symbol NAME version VERSION not defined in file FILE with link time reference"
+        LoadErrorKind::UndefinedSymbol { name: name_after("symbol ").unwrap_or_default() }
+    } else if let Some(name) = name_after("Library not loaded: ") {
+        LoadErrorKind::MissingDependency { name }
+    } else if msg.contains("wrong ELF class")
+        || msg.contains("ELF file data encoding")
+        || msg.contains("incompatible architecture")
+    {
+        LoadErrorKind::ArchitectureMismatch
+    } else if msg.contains("invalid ELF header")
+        || msg.contains("file too short")
+        || msg.contains("only ET_DYN and ET_EXEC")
+        || msg.contains("Exec format error")
+        || msg.contains("not a mach-o file")
+    {
+        LoadErrorKind::InvalidFormat
+    } else if msg.contains("Permission denied") {
+        LoadErrorKind::PermissionDenied
+    } else if msg.contains("No such file or directory") || msg.contains("image not found") {
+        // glibc and musl prefix the message with the file that couldn't be opened,
+        // which is a dependency if it isn't the library we asked for
+        match msg.split_once(": ") {
+            Some((subject, _)) if Path::new(subject) != path => {
+                LoadErrorKind::MissingDependency { name: subject.to_owned() }
+            }
+            _ => LoadErrorKind::NotFound,
+        }
+    } else {
+        LoadErrorKind::Other
+    }
+}
+
+/// Classifies a load error by inspecting the library file, if the error lies with the file itself.
+fn classify_library_file(path: &Path) -> Option<LoadErrorKind> {
+    // the dynamic linker searches for names without a slash, so there is no single file to inspect
+    if !path.as_os_str().as_bytes().contains(&b'/') {
+        return None;
+    }
+    match std::fs::File::open(path) {
+        Ok(_) => (),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Some(LoadErrorKind::NotFound),
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            return Some(LoadErrorKind::PermissionDenied)
+        }
+        Err(_) => return None,
+    }
+    #[cfg(target_os = "linux")]
+    match crate::elf::ElfLibrary::open(path) {
+        Err(crate::ElfError::BadMagic | crate::ElfError::Truncated) => {
+            return Some(LoadErrorKind::InvalidFormat)
+        }
+        Ok(library) if !crate::preflight::matches_host(&library) => {
+            return Some(LoadErrorKind::ArchitectureMismatch)
+        }
+        _ => (),
+    }
+    None
+}
+
+/// Returns true if an error returned by [`get_symbol`] means the symbol doesn't exist.
+///
+/// Lookup failures are reported as [`io::ErrorKind::NotFound`]. Errors without that kind
+/// fall back to the dynamic linker's message.
+pub(crate) fn is_symbol_not_found(error: &io::Error) -> bool {
+    if error.kind() == io::ErrorKind::NotFound {
+        return true;
+    }
+    let msg = error.to_string();
+    // glibc: "undefined symbol: NAME", musl: "Symbol not found: NAME",
+    // macOS: "dlsym(HANDLE, NAME): symbol not found"
+    msg.contains("undefined symbol") || msg.contains("ymbol not found")
+}
+
 /// Gets a symbol from a path.
 ///
 /// This is equivalent to:
@@ -73,16 +476,63 @@ pub unsafe fn load_library(path: &OsStr) -> Result<*mut c_void, LoadError> {
 /// dlsym(handle, symbol);
 /// ```
 /// with additional error checking.
+/// # Safety
+/// `handle` must be a valid library handle.
 pub unsafe fn get_symbol(handle: Handle, symbol: &CStr) -> io::Result<*mut c_void> {
     with_dlerror_lock(|| {
         let _ = dlerror();
         let symbol = dlsym(handle, symbol.as_ptr());
 
+        if symbol.is_null() {
+            let msg = dlerror();
+            if !msg.is_null() {
+                // with a valid handle, the only way for the lookup to fail is a missing symbol
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    CStr::from_ptr(msg).to_string_lossy().into_owned(),
+                ));
+            }
+        }
+
+        Ok(symbol)
+    })
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+extern "C" {
+    fn dlvsym(
+        handle: *mut c_void,
+        symbol: *const std::ffi::c_char,
+        version: *const std::ffi::c_char,
+    ) -> *mut c_void;
+}
+
+/// Gets a specific version of a symbol from a path.
+///
+/// This is equivalent to:
+/// ```c
+/// #define _GNU_SOURCE
+/// #include <dlfcn.h>
+/// dlvsym(handle, symbol, version);
+/// ```
+/// with additional error checking.
+/// # Safety
+/// `handle` must be a valid library handle.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+pub unsafe fn get_versioned_symbol(
+    handle: Handle,
+    symbol: &CStr,
+    version: &CStr,
+) -> io::Result<*mut c_void> {
+    with_dlerror_lock(|| {
+        let _ = dlerror();
+        let symbol = dlvsym(handle, symbol.as_ptr(), version.as_ptr());
+
         if symbol.is_null() {
             let msg = dlerror();
             if !msg.is_null() {
                 return Err(io::Error::new(
-                    io::ErrorKind::Other,
+                    io::ErrorKind::NotFound,
                     CStr::from_ptr(msg).to_string_lossy().into_owned(),
                 ));
             }
@@ -100,12 +550,34 @@ pub unsafe fn get_symbol(handle: Handle, symbol: &CStr) -> io::Result<*mut c_voi
 /// dlclose(handle);
 /// ```
 /// with additional error checking.
-pub fn free_library(handle: Handle) {
-    if unsafe { dlclose(handle) } != 0 {
-        panic!("dlclose() failed!");
-    }
+/// # Safety
+/// `handle` must be a valid library handle, and must not be used after this call.
+pub unsafe fn free_library(handle: Handle) -> io::Result<()> {
+    with_dlerror_lock(|| {
+        if dlclose(handle) != 0 {
+            let msg = dlerror();
+            let msg = if msg.is_null() {
+                "dlclose() failed".to_owned()
+            } else {
+                CStr::from_ptr(msg).to_string_lossy().into_owned()
+            };
+            return Err(io::Error::other(msg));
+        }
+
+        Ok(())
+    })
 }
 
+/// Obtains dynamic-linker information from a memory address.
+///
+/// This is equivalent to:
+/// ```c
+/// #include <dlfcn.h>
+/// Dl_info info;
+/// dladdr(ptr, &info);
+/// ```
+/// # Safety
+/// The dynamic linker must not unload the library containing `ptr` during this call.
 pub unsafe fn get_address_info(ptr: *const c_void) -> Option<AddressInfo> {
     let mut info = libc::Dl_info {
         dli_fname: null(),
@@ -130,3 +602,335 @@ pub unsafe fn get_address_info(ptr: *const c_void) -> Option<AddressInfo> {
         None
     }
 }
+
+/// An ELF program header of the host's word size.
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+pub type ProgramHeader = libc::Elf64_Phdr;
+/// An ELF program header of the host's word size.
+#[cfg(all(target_os = "linux", target_pointer_width = "32"))]
+pub type ProgramHeader = libc::Elf32_Phdr;
+
+/// Lists the modules currently loaded in the process.
+///
+/// This is equivalent to:
+/// ```c
+/// #define _GNU_SOURCE
+/// #include <link.h>
+/// dl_iterate_phdr(callback, data);
+/// ```
+/// where `callback` copies each `dl_phdr_info` into a [`ModuleInfo`].
+#[cfg(target_os = "linux")]
+pub fn loaded_modules() -> Vec<ModuleInfo> {
+    unsafe extern "C" fn callback(
+        info: *mut libc::dl_phdr_info,
+        _size: usize,
+        data: *mut c_void,
+    ) -> c_int {
+        let modules = &mut *(data as *mut Vec<ModuleInfo>);
+        let info = &*info;
+
+        let path = Path::new(OsStr::from_bytes(CStr::from_ptr(info.dlpi_name).to_bytes())).to_owned();
+        let program_headers = if info.dlpi_phdr.is_null() {
+            Vec::new()
+        } else {
+            std::slice::from_raw_parts(info.dlpi_phdr, info.dlpi_phnum as usize).to_vec()
+        };
+
+        modules.push(ModuleInfo {
+            path,
+            base_addr: info.dlpi_addr as usize as *const c_void,
+            program_headers,
+            tls_module_id: std::num::NonZeroUsize::new(info.dlpi_tls_modid),
+        });
+        0
+    }
+
+    let mut modules = Vec::new();
+    unsafe { libc::dl_iterate_phdr(Some(callback), &mut modules as *mut Vec<ModuleInfo> as *mut c_void) };
+    modules
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[repr(C)]
+struct LinkMap {
+    l_addr: usize,
+    l_name: *const std::ffi::c_char,
+    l_ld: *const ElfDyn,
+    l_next: *mut LinkMap,
+    l_prev: *mut LinkMap,
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct ElfDyn {
+    d_tag: isize,
+    d_val: usize,
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+const DT_NULL: isize = 0;
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+const DT_NEEDED: isize = 1;
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+const DT_STRTAB: isize = 5;
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+const DT_SONAME: isize = 14;
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+const DT_HASH: isize = 4;
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+const DT_SYMTAB: isize = 6;
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+const DT_GNU_HASH: isize = 0x6fff_fef5;
+
+#[cfg(all(target_os = "linux", target_env = "gnu", target_pointer_width = "64"))]
+type ElfSym = libc::Elf64_Sym;
+#[cfg(all(target_os = "linux", target_env = "gnu", target_pointer_width = "32"))]
+type ElfSym = libc::Elf32_Sym;
+
+/// Gets a library's link map entry.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+unsafe fn get_link_map<'a>(handle: Handle) -> io::Result<&'a LinkMap> {
+    // glibc returns pseudo-handles as-is instead of failing
+    if is_pseudo_handle(handle) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "pseudo-handles do not have a link map",
+        ));
+    }
+    with_dlerror_lock(|| {
+        let mut map: *const LinkMap = null();
+        if libc::dlinfo(handle, libc::RTLD_DI_LINKMAP, &mut map as *mut *const LinkMap as *mut c_void) != 0 {
+            let msg = dlerror();
+            return Err(io::Error::other(
+                CStr::from_ptr(msg).to_string_lossy().into_owned(),
+            ));
+        }
+        map.as_ref()
+            .ok_or_else(|| io::Error::other("dlinfo returned no link map"))
+    })
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+impl LinkMap {
+    /// Reads the entries of the in-memory dynamic section.
+    unsafe fn dynamic(&self) -> Vec<ElfDyn> {
+        let mut dynamic = Vec::new();
+        if !self.l_ld.is_null() {
+            let mut entry = self.l_ld;
+            while (*entry).d_tag != DT_NULL {
+                dynamic.push(*entry);
+                entry = entry.add(1);
+            }
+        }
+        dynamic
+    }
+
+    /// Gets the address stored in a dynamic section entry.
+    fn address_of(&self, dynamic: &[ElfDyn], tag: isize) -> Option<usize> {
+        // glibc usually relocates the in-memory dynamic section, but not on every architecture
+        dynamic.iter().find(|entry| entry.d_tag == tag).map(|entry| {
+            if entry.d_val < self.l_addr {
+                entry.d_val + self.l_addr
+            } else {
+                entry.d_val
+            }
+        })
+    }
+}
+
+/// Information from a library's link map entry and dynamic section.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[derive(Debug, Clone)]
+pub struct LinkMapInfo {
+    /// The path the library was loaded from. This is empty for the main program.
+    pub path: std::path::PathBuf,
+    /// The difference between the library's load address and the addresses in its ELF file.
+    pub base_addr: *const c_void,
+    /// The library's `DT_SONAME`, if it has one.
+    pub soname: Option<CString>,
+    /// The library's `DT_NEEDED` entries, in order.
+    pub needed: Vec<CString>,
+}
+
+/// Gets information about a library from its link map entry.
+///
+/// This is equivalent to:
+/// ```c
+/// #define _GNU_SOURCE
+/// #include <dlfcn.h>
+/// #include <link.h>
+/// struct link_map* map;
+/// dlinfo(handle, RTLD_DI_LINKMAP, &map);
+/// ```
+/// followed by reading `map->l_ld`, the library's dynamic section.
+/// # Safety
+/// `handle` must be a valid library handle, and not a pseudo-handle.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+pub unsafe fn get_link_map_info(handle: Handle) -> io::Result<LinkMapInfo> {
+    let map = get_link_map(handle)?;
+
+    let path = Path::new(OsStr::from_bytes(CStr::from_ptr(map.l_name).to_bytes())).to_owned();
+
+    let dynamic = map.dynamic();
+    let strtab = map.address_of(&dynamic, DT_STRTAB);
+    let string_at = |offset: usize| {
+        strtab.map(|strtab| CStr::from_ptr((strtab + offset) as *const std::ffi::c_char).to_owned())
+    };
+
+    let soname = dynamic
+        .iter()
+        .find(|entry| entry.d_tag == DT_SONAME)
+        .and_then(|entry| string_at(entry.d_val));
+    let needed = dynamic
+        .iter()
+        .filter(|entry| entry.d_tag == DT_NEEDED)
+        .filter_map(|entry| string_at(entry.d_val))
+        .collect();
+
+    Ok(LinkMapInfo {
+        path,
+        base_addr: map.l_addr as *const c_void,
+        soname,
+        needed,
+    })
+}
+
+/// Gets the names of the symbols a library exports, by reading its dynamic symbol table from memory.
+///
+/// The number of symbols is determined from the library's GNU or System V hash table.
+/// If it has neither, this returns an empty list.
+/// # Safety
+/// `handle` must be a valid library handle, and not a pseudo-handle.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+pub unsafe fn get_exported_symbols(handle: Handle) -> io::Result<Vec<CString>> {
+    let map = get_link_map(handle)?;
+    let dynamic = map.dynamic();
+
+    let (Some(symtab), Some(strtab)) = (
+        map.address_of(&dynamic, DT_SYMTAB),
+        map.address_of(&dynamic, DT_STRTAB),
+    ) else {
+        return Ok(Vec::new());
+    };
+    let count = if let Some(hash) = map.address_of(&dynamic, DT_GNU_HASH) {
+        gnu_hash_symbol_count(hash as *const u32)
+    } else if let Some(hash) = map.address_of(&dynamic, DT_HASH) {
+        // nchain is the number of symbols
+        *(hash as *const u32).add(1) as usize
+    } else {
+        return Ok(Vec::new());
+    };
+
+    let symbols = std::slice::from_raw_parts(symtab as *const ElfSym, count);
+    Ok(symbols
+        .iter()
+        // skip undefined and local symbols
+        .filter(|sym| sym.st_shndx != 0 && sym.st_info >> 4 != 0)
+        .map(|sym| CStr::from_ptr((strtab + sym.st_name as usize) as *const std::ffi::c_char))
+        .filter(|name| !name.is_empty())
+        .map(CStr::to_owned)
+        .collect())
+}
+
+/// Counts the symbols covered by a GNU hash table, by finding the end of its last chain.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+unsafe fn gnu_hash_symbol_count(hash: *const u32) -> usize {
+    let nbuckets = *hash as usize;
+    let symoffset = *hash.add(1) as usize;
+    let bloom_size = *hash.add(2) as usize;
+
+    let buckets = hash.add(4).cast::<usize>().add(bloom_size).cast::<u32>();
+    let chains = buckets.add(nbuckets);
+
+    let last = (0..nbuckets).map(|i| *buckets.add(i) as usize).max().unwrap_or(0);
+    if last < symoffset {
+        return symoffset;
+    }
+    let mut index = last;
+    // the low bit marks the end of a chain
+    while *chains.add(index - symoffset) & 1 == 0 {
+        index += 1;
+    }
+    index + 1
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+extern "C" {
+    fn dladdr1(
+        addr: *const c_void,
+        info: *mut libc::Dl_info,
+        extra_info: *mut *mut c_void,
+        flags: c_int,
+    ) -> c_int;
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+const RTLD_DL_LINKMAP: c_int = 2;
+
+/// Gets the path of a link map entry. The main program's entry has an empty path,
+/// so the path of the current executable is used instead.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+unsafe fn link_map_path(map: &LinkMap) -> std::path::PathBuf {
+    let name = CStr::from_ptr(map.l_name).to_bytes();
+    if name.is_empty() {
+        std::env::current_exe().unwrap_or_default()
+    } else {
+        Path::new(OsStr::from_bytes(name)).to_owned()
+    }
+}
+
+/// Checks that a symbol address belongs to the library `handle` refers to,
+/// rather than one of its dependencies or an interposing library.
+///
+/// This compares `handle`'s link map entry against the one returned by:
+/// ```c
+/// #define _GNU_SOURCE
+/// #include <dlfcn.h>
+/// #include <link.h>
+/// Dl_info info;
+/// struct link_map* map;
+/// dladdr1(ptr, &info, (void**) &map, RTLD_DL_LINKMAP);
+/// ```
+///
+/// Pseudo-handles have no link map entry, so this fails for them.
+/// # Safety
+/// `handle` must be a valid library handle or a pseudo-handle.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+pub(crate) unsafe fn verify_provenance(handle: Handle, ptr: *const c_void) -> Result<(), SymbolError> {
+    let expected = get_link_map(handle)?;
+
+    let mut info: libc::Dl_info = std::mem::zeroed();
+    let mut actual: *const LinkMap = null();
+    let found = dladdr1(
+        ptr,
+        &mut info,
+        &mut actual as *mut *const LinkMap as *mut *mut c_void,
+        RTLD_DL_LINKMAP,
+    ) != 0;
+
+    if found && std::ptr::eq(expected, actual) {
+        return Ok(());
+    }
+    Err(SymbolError::WrongProvenance {
+        expected: link_map_path(expected),
+        actual: if found && !actual.is_null() {
+            link_map_path(&*actual)
+        } else {
+            std::path::PathBuf::new()
+        },
+    })
+}
+
+/// Checks that a symbol address belongs to the library `handle` refers to.
+///
+/// This is only supported on glibc, so it always fails elsewhere.
+/// # Safety
+/// This function is always safe to call.
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+pub(crate) unsafe fn verify_provenance(_handle: Handle, _ptr: *const c_void) -> Result<(), SymbolError> {
+    Err(SymbolError::Os(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "strict symbol resolution is not supported on this platform",
+    )))
+}
@@ -1,22 +1,61 @@
 use std::{
-    ffi::{c_void, CStr, NulError, OsStr},
+    ffi::{c_void, CStr, NulError, OsStr, OsString},
     io, iter, mem,
-    os::windows::ffi::OsStrExt,
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
 };
 
 use windows::{
     core::{Error as WinError, PCSTR, PCWSTR},
     Win32::{
-        Foundation::{FreeLibrary, HMODULE},
-        System::LibraryLoader::{GetProcAddress, LoadLibraryW},
+        Foundation::{FreeLibrary, HANDLE, HMODULE},
+        System::LibraryLoader::{
+            GetModuleFileNameW, GetModuleHandleExW, GetModuleHandleW, GetProcAddress, LoadLibraryExW,
+            GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS, GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+            LOAD_LIBRARY_FLAGS,
+        },
     },
 };
 
-use crate::{LoadError, WideNulError};
+use crate::{LoadError, LoadErrorKind, SymbolError, WideNulError};
+
+use super::AddressInfo;
 
 /// The Windows dynamic library handle, `HMODULE`.
 pub type Handle = HMODULE;
 
+/// Flags passed to `LoadLibraryExW()` when loading a library.
+///
+/// The default options pass no flags, which is what [`load_library`] uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LoadOptions {
+    flags: LOAD_LIBRARY_FLAGS,
+}
+
+impl LoadOptions {
+    /// Creates the default set of options (no flags).
+    pub const fn new() -> Self {
+        Self { flags: LOAD_LIBRARY_FLAGS(0) }
+    }
+
+    /// Adds the given `LOAD_LIBRARY_*` flags.
+    pub const fn with_flags(mut self, flags: LOAD_LIBRARY_FLAGS) -> Self {
+        self.flags = LOAD_LIBRARY_FLAGS(self.flags.0 | flags.0);
+        self
+    }
+
+    /// Returns the flags that will be passed to `LoadLibraryExW()`.
+    pub const fn flags(&self) -> LOAD_LIBRARY_FLAGS {
+        self.flags
+    }
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Loads a library from a path. 
 /// 
 /// This is equivalent to:
@@ -26,12 +65,60 @@ pub type Handle = HMODULE;
 /// ```
 /// with additional error checking.
 pub unsafe fn load_library(path: &OsStr) -> Result<Handle, LoadError> {
+    load_library_with(path, &LoadOptions::new())
+}
+
+/// Loads a library from a path using the provided options.
+/// 
+/// This is equivalent to:
+/// ```c
+/// #include <windows.h>
+/// LoadLibraryExW(path, NULL, flags);
+/// ```
+/// with additional error checking.
+pub unsafe fn load_library_with(path: &OsStr, options: &LoadOptions) -> Result<Handle, LoadError> {
     // collect to wchar_t
-    let wstr: Vec<u16> = path.encode_wide().collect();
+    let mut wstr: Vec<u16> = path.encode_wide().collect();
     if let Some(pos) = wstr.iter().position(|&b| b == 0) {
         return Err(WideNulError(pos, wstr).into());
     }
-    LoadLibraryW(PCWSTR::from_raw(wstr.as_ptr())).map_err(|err| to_io_error(err).into())
+    wstr.push(0);
+    LoadLibraryExW(PCWSTR::from_raw(wstr.as_ptr()), HANDLE::default(), options.flags())
+        .map_err(|err| to_io_error(err).into())
+}
+
+/// Obtains a handle to the main program.
+/// 
+/// This is equivalent to:
+/// ```c
+/// #include <windows.h>
+/// GetModuleHandleW(NULL);
+/// ```
+/// with additional error checking. The returned handle is not reference-counted
+/// and must not be passed to [`free_library`].
+pub unsafe fn open_self(_options: &LoadOptions) -> Result<Handle, LoadError> {
+    GetModuleHandleW(PCWSTR::null()).map_err(|err| to_io_error(err).into())
+}
+
+/// Obtains a handle to a library only if it is already loaded.
+/// 
+/// This is equivalent to:
+/// ```c
+/// #include <windows.h>
+/// HMODULE handle;
+/// GetModuleHandleExW(0, name, &handle);
+/// ```
+/// If a handle is returned, the library's reference count has been incremented.
+pub unsafe fn get_loaded_library(name: &OsStr) -> Result<Option<Handle>, LoadError> {
+    let mut wstr: Vec<u16> = name.encode_wide().collect();
+    if let Some(pos) = wstr.iter().position(|&b| b == 0) {
+        return Err(WideNulError(pos, wstr).into());
+    }
+    wstr.push(0);
+    let mut handle = HMODULE::default();
+    Ok(GetModuleHandleExW(0, PCWSTR::from_raw(wstr.as_ptr()), &mut handle)
+        .ok()
+        .map(|()| handle))
 }
 
 /// Gets a symbol from a path. 
@@ -50,6 +137,48 @@ pub unsafe fn get_symbol(handle: Handle, symbol: &CStr) -> io::Result<*mut c_voi
     Ok(mem::transmute::<_, *mut c_void>(symbol))
 }
 
+/// Returns true if an error returned by [`get_symbol`] means the symbol doesn't exist.
+pub(crate) fn is_symbol_not_found(error: &io::Error) -> bool {
+    const ERROR_PROC_NOT_FOUND: i32 = 127;
+    error.raw_os_error() == Some(ERROR_PROC_NOT_FOUND)
+}
+
+/// Checks that a symbol address belongs to the module `handle` refers to,
+/// rather than a module its export was forwarded to.
+///
+/// This compares `handle` against the module returned by:
+/// ```c
+/// #include <windows.h>
+/// HMODULE module;
+/// GetModuleHandleExW(
+///     GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+///     (LPCWSTR) ptr, &module
+/// );
+/// ```
+pub(crate) unsafe fn verify_provenance(handle: Handle, ptr: *const c_void) -> Result<(), SymbolError> {
+    let mut actual = HMODULE::default();
+    GetModuleHandleExW(
+        GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+        PCWSTR::from_raw(ptr as *const u16),
+        &mut actual,
+    )
+    .map_err(to_io_error)?;
+
+    if actual == handle {
+        return Ok(());
+    }
+    Err(SymbolError::WrongProvenance {
+        expected: module_path(handle),
+        actual: module_path(actual),
+    })
+}
+
+fn module_path(module: HMODULE) -> PathBuf {
+    let mut buf = vec![0u16; 1024];
+    let len = unsafe { GetModuleFileNameW(module, &mut buf) } as usize;
+    PathBuf::from(OsString::from_wide(&buf[..len]))
+}
+
 /// Closes a library. 
 /// 
 /// This is equivalent to:
@@ -58,8 +187,35 @@ pub unsafe fn get_symbol(handle: Handle, symbol: &CStr) -> io::Result<*mut c_voi
 /// FreeLibrary(handle);
 /// ```
 /// with additional error checking.
-pub fn free_library(handle: Handle) {
-    unsafe { FreeLibrary(handle).expect("FreeLibrary failed!") }
+pub unsafe fn free_library(handle: Handle) -> io::Result<()> {
+    FreeLibrary(handle).map_err(to_io_error)
+}
+
+/// Classifies an error returned by [`load_library`] using its error code.
+pub(crate) fn classify_load_error(_path: &Path, error: &io::Error) -> LoadErrorKind {
+    const ERROR_FILE_NOT_FOUND: i32 = 2;
+    const ERROR_PATH_NOT_FOUND: i32 = 3;
+    const ERROR_ACCESS_DENIED: i32 = 5;
+    const ERROR_BAD_FORMAT: i32 = 11;
+    const ERROR_MOD_NOT_FOUND: i32 = 126;
+    const ERROR_PROC_NOT_FOUND: i32 = 127;
+    const ERROR_BAD_EXE_FORMAT: i32 = 193;
+
+    match error.raw_os_error() {
+        Some(ERROR_FILE_NOT_FOUND | ERROR_PATH_NOT_FOUND | ERROR_MOD_NOT_FOUND) => {
+            LoadErrorKind::NotFound
+        }
+        Some(ERROR_ACCESS_DENIED) => LoadErrorKind::PermissionDenied,
+        Some(ERROR_BAD_FORMAT) => LoadErrorKind::InvalidFormat,
+        Some(ERROR_BAD_EXE_FORMAT) => LoadErrorKind::ArchitectureMismatch,
+        // Windows doesn't report which import failed to resolve
+        Some(ERROR_PROC_NOT_FOUND) => LoadErrorKind::UndefinedSymbol { name: String::new() },
+        _ => match error.kind() {
+            io::ErrorKind::NotFound => LoadErrorKind::NotFound,
+            io::ErrorKind::PermissionDenied => LoadErrorKind::PermissionDenied,
+            _ => LoadErrorKind::Other,
+        },
+    }
 }
 
 fn to_io_error(err: WinError) -> io::Error {
@@ -0,0 +1,195 @@
+use std::{
+    marker::PhantomData,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicPtr, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+use crate::{can::Can, raw::Library, ReloadError, SymbolGroup};
+
+/// A [`Can`] that can be replaced while other threads are using it, without readers
+/// ever blocking.
+///
+/// Readers call [`SwapCan::load`] to obtain a [`SwapGuard`] to the current can, which
+/// only takes a few atomic operations. Writers publish a new can with [`SwapCan::store`]
+/// or [`SwapCan::reload`]. The previous can is retired rather than dropped, and its
+/// library is only unloaded once the last guard to it has been dropped, so no call can
+/// be executing inside a library when it is closed.
+///
+/// Retired cans are unloaded by writers (including [`SwapCan::collect`]), never by
+/// readers, so dropping a guard never closes a library on a reader's thread.
+/// ```no_run
+/// # use decan::{can::Can, SwapCan, SymbolGroup};
+/// #[derive(SymbolGroup)]
+/// struct Dsp {
+///     dsp_process: extern "C" fn(*mut f32, usize),
+/// }
+///
+/// let dsp = SwapCan::new(unsafe { Can::<_, Dsp>::load("libdsp.so").unwrap() });
+/// std::thread::scope(|scope| {
+///     scope.spawn(|| {
+///         let mut buffer = [0.0; 256];
+///         // on the audio thread
+///         dsp.load().symbols().dsp_process.call(buffer.as_mut_ptr(), buffer.len());
+///     });
+///     // on another thread, after the library was rebuilt
+///     unsafe { dsp.reload().unwrap() };
+/// });
+/// ```
+pub struct SwapCan<G: SymbolGroup> {
+    /// The current can, created with [`Arc::into_raw`].
+    current: AtomicPtr<Can<Library, G>>,
+    /// Incremented by writers after replacing `current`.
+    epoch: AtomicUsize,
+    /// The number of readers that may be reading `current` in an even or odd epoch.
+    readers: [AtomicUsize; 2],
+    /// Cans that were replaced, but may still be held by guards. This also serializes writers.
+    retired: Mutex<Vec<Arc<Can<Library, G>>>>,
+}
+
+impl<G: SymbolGroup> SwapCan<G> {
+    /// Wraps a can so that it can be swapped.
+    pub fn new(can: Can<Library, G>) -> Self {
+        Self {
+            current: AtomicPtr::new(Arc::into_raw(Arc::new(can)).cast_mut()),
+            epoch: AtomicUsize::new(0),
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            retired: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns a guard to the current can. This never blocks.
+    pub fn load(&self) -> SwapGuard<'_, G> {
+        loop {
+            // announce the read in the current epoch, so that writers wait for it
+            let epoch = self.epoch.load(Ordering::SeqCst);
+            let readers = &self.readers[epoch % 2];
+            readers.fetch_add(1, Ordering::SeqCst);
+            if self.epoch.load(Ordering::SeqCst) != epoch {
+                // a writer started waiting before the read was announced, so try again
+                readers.fetch_sub(1, Ordering::SeqCst);
+                continue;
+            }
+
+            let current = self.current.load(Ordering::SeqCst);
+            // SAFETY: writers don't release `current` until the announced reads are done
+            let can = unsafe {
+                Arc::increment_strong_count(current);
+                Arc::from_raw(current)
+            };
+            readers.fetch_sub(1, Ordering::SeqCst);
+            return SwapGuard {
+                can,
+                marker: PhantomData,
+            };
+        }
+    }
+
+    /// Publishes a new can. Readers that load the can from now on get the new one,
+    /// while the previous one is retired until no guard to it is left.
+    pub fn store(&self, can: Can<Library, G>) {
+        self.publish(&mut self.lock_retired(), can);
+    }
+
+    /// Loads a new version of the current can's library and publishes it.
+    /// See [`Can::reload`] for how the library is reloaded and its state handed over.
+    ///
+    /// If reloading fails, the current can stays published.
+    ///
+    /// Readers are not paused while the library is reloaded, so the previous library's
+    /// `save_state` and the new library's `restore_state` may run while other threads are
+    /// still calling into the previous library. The state they hand over must be safe to
+    /// read while it is being modified, or the library must synchronize access to it.
+    /// # Safety
+    /// The caller is responsible for ensuring that the symbols specified in `G`
+    /// match exported library symbols with the correct type, and that `save_state`
+    /// is safe to call concurrently with the library's other exports.
+    pub unsafe fn reload(&self) -> Result<(), ReloadError> {
+        // hold the writer lock, so that the state of each version is only handed over once
+        let mut retired = self.lock_retired();
+        let reloaded = self.load().reloaded()?;
+        self.publish(&mut retired, reloaded);
+        Ok(())
+    }
+
+    fn publish(&self, retired: &mut Vec<Arc<Can<Library, G>>>, can: Can<Library, G>) {
+        let new = Arc::into_raw(Arc::new(can)).cast_mut();
+        let old = self.current.swap(new, Ordering::SeqCst);
+
+        // wait for readers that may have seen the old can to take their reference
+        let epoch = self.epoch.fetch_add(1, Ordering::SeqCst);
+        while self.readers[epoch % 2].load(Ordering::SeqCst) != 0 {
+            std::hint::spin_loop();
+        }
+
+        // SAFETY: `old` was created with `Arc::into_raw`, and no reader can access it anymore
+        retired.push(unsafe { Arc::from_raw(old) });
+        Self::collect_retired(retired);
+    }
+
+    /// Unloads retired cans that are no longer held by any guard.
+    pub fn collect(&self) {
+        Self::collect_retired(&mut self.lock_retired());
+    }
+
+    /// Returns the number of retired cans that are still held by a guard.
+    pub fn retired_count(&self) -> usize {
+        self.lock_retired().len()
+    }
+
+    fn lock_retired(&self) -> MutexGuard<'_, Vec<Arc<Can<Library, G>>>> {
+        self.retired.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn collect_retired(retired: &mut Vec<Arc<Can<Library, G>>>) {
+        retired.retain(|can| Arc::strong_count(can) > 1);
+    }
+}
+
+impl<G: SymbolGroup> Drop for SwapCan<G> {
+    fn drop(&mut self) {
+        // SAFETY: `current` was created with `Arc::into_raw`, and guards borrow `self`
+        drop(unsafe { Arc::from_raw(*self.current.get_mut()) });
+    }
+}
+
+impl<G: SymbolGroup> std::fmt::Debug for SwapCan<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SwapCan")
+            .field("epoch", &self.epoch.load(Ordering::Relaxed))
+            .field("retired", &self.retired_count())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A reference to the can that was current when [`SwapCan::load`] was called.
+///
+/// The can's library stays loaded while the guard exists, even if a new can is published.
+/// Symbols obtained through [`Can::symbols`] borrow the guard, so they can't be used
+/// once it is dropped:
+/// ```compile_fail,E0505
+/// # use decan::{can::Can, SwapCan, SymbolGroup};
+/// # #[derive(SymbolGroup)]
+/// # struct Dsp {
+/// #     dsp_gain: extern "C" fn(f32) -> f32,
+/// # }
+/// let dsp = SwapCan::new(unsafe { Can::<_, Dsp>::load("libdsp.so").unwrap() });
+/// let guard = dsp.load();
+/// let gain = guard.symbols().dsp_gain;
+/// drop(guard);
+/// gain.call(0.5);
+/// ```
+pub struct SwapGuard<'a, G: SymbolGroup> {
+    can: Arc<Can<Library, G>>,
+    marker: PhantomData<&'a SwapCan<G>>,
+}
+
+impl<G: SymbolGroup> Deref for SwapGuard<'_, G> {
+    type Target = Can<Library, G>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.can
+    }
+}
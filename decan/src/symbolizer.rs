@@ -0,0 +1,252 @@
+use std::{
+    ffi::c_void,
+    ops::Range,
+    path::Path,
+    sync::Arc,
+};
+
+use crate::{
+    elf::{ElfLibrary, SymbolType},
+    raw,
+};
+
+/// The symbol nearest to an address resolved by a [`Symbolizer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolLocation {
+    name: Arc<str>,
+    offset: usize,
+    size: Option<u64>,
+}
+
+impl SymbolLocation {
+    /// The name of the symbol.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The offset of the address from the start of the symbol.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The size of the symbol in bytes, if known.
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+}
+
+/// An address resolved by a [`Symbolizer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolizedAddress {
+    module_path: Arc<Path>,
+    module_offset: usize,
+    symbol: Option<SymbolLocation>,
+}
+
+impl SymbolizedAddress {
+    /// The path of the module containing the address.
+    pub fn module_path(&self) -> &Path {
+        &self.module_path
+    }
+
+    /// The offset of the address from the module's base address. This is the address
+    /// as it appears in the module's ELF file, e.g. for use with `addr2line`.
+    pub fn module_offset(&self) -> usize {
+        self.module_offset
+    }
+
+    /// The exported symbol containing the address, if one was found.
+    pub fn symbol(&self) -> Option<&SymbolLocation> {
+        self.symbol.as_ref()
+    }
+}
+
+/// A symbol read from a module's file, relative to the module's base address.
+struct CachedSymbol {
+    start: u64,
+    size: u64,
+    name: Arc<str>,
+}
+
+struct CachedModule {
+    path: Arc<Path>,
+    base: usize,
+    segments: Vec<Range<usize>>,
+    /// The module's symbols sorted by address, read the first time they are needed.
+    symbols: Option<Vec<CachedSymbol>>,
+}
+
+impl CachedModule {
+    fn new(module: raw::ModuleInfo) -> Self {
+        let base = module.base_addr as usize;
+        let segments = module
+            .program_headers
+            .iter()
+            .filter(|header| header.p_type == libc::PT_LOAD)
+            .map(|header| {
+                let start = base.wrapping_add(header.p_vaddr as usize);
+                start..start + header.p_memsz as usize
+            })
+            .collect();
+        let path = if module.is_main_program() {
+            std::env::current_exe().unwrap_or_default()
+        } else {
+            module.path
+        };
+        Self {
+            path: path.into(),
+            base,
+            segments,
+            symbols: None,
+        }
+    }
+
+    fn contains(&self, addr: usize) -> bool {
+        self.segments.iter().any(|segment| segment.contains(&addr))
+    }
+
+    fn symbols(&mut self) -> &[CachedSymbol] {
+        self.symbols.get_or_insert_with(|| {
+            let Ok(library) = ElfLibrary::open(&self.path) else {
+                return Vec::new();
+            };
+            let mut symbols: Vec<CachedSymbol> = library
+                .symbols()
+                .iter()
+                .filter(|sym| sym.is_defined() && sym.value() != 0)
+                .filter(|sym| !matches!(sym.kind(), SymbolType::Section | SymbolType::File | SymbolType::Tls))
+                .map(|sym| CachedSymbol {
+                    start: sym.value(),
+                    size: sym.size(),
+                    name: sym.name().into(),
+                })
+                .collect();
+            // for aliases at the same address, prefer the one with a known size
+            symbols.sort_by_key(|sym| (sym.start, sym.size == 0));
+            symbols.dedup_by_key(|sym| sym.start);
+            symbols
+        })
+    }
+}
+
+/// Resolves addresses to modules and symbols, caching each module's symbol table.
+///
+/// Symbols are read from each module's dynamic symbol table the first time an address
+/// in that module is resolved, so resolving many addresses is cheap. If a module's file
+/// can't be read, this falls back to [`raw::get_address_info`].
+///
+/// Only exported symbols (`.dynsym`) are known, not local ones. An address in a local
+/// function is resolved to no symbol if it follows an export of known size, but to that
+/// export if its size is zero (e.g. hand-written assembly), since there is no way to tell
+/// where a zero-size symbol ends.
+/// ```
+/// # use decan::Symbolizer;
+/// let mut symbolizer = Symbolizer::new();
+/// let addr = libc::strlen as *const std::ffi::c_void;
+/// let resolved = symbolizer.symbolize(addr).unwrap();
+/// println!("{}+{:#x}", resolved.module_path().display(), resolved.module_offset());
+/// ```
+///
+/// The cache is keyed by module, and is refreshed whenever an address doesn't belong
+/// to any known module. If libraries are unloaded, call [`Symbolizer::clear`] so that
+/// new libraries loaded at the same address aren't mistaken for the old ones.
+#[derive(Default)]
+pub struct Symbolizer {
+    modules: Vec<CachedModule>,
+}
+
+impl Symbolizer {
+    /// Creates a symbolizer with an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolves an address to its module and nearest symbol.
+    ///
+    /// Returns `None` if the address doesn't belong to any loaded module.
+    ///
+    /// This is not async-signal-safe: it allocates, reads files and takes the dynamic
+    /// linker's lock through `dl_iterate_phdr(3)`, so it must not be called from a signal
+    /// handler. To symbolize a crash, record the addresses in the handler and symbolize
+    /// them afterwards, e.g. from another process.
+    pub fn symbolize(&mut self, addr: *const c_void) -> Option<SymbolizedAddress> {
+        let addr = addr as usize;
+        let index = match self.find_module(addr) {
+            Some(index) => index,
+            None => {
+                self.refresh();
+                self.find_module(addr)?
+            }
+        };
+        let module = &mut self.modules[index];
+        let module_offset = addr.wrapping_sub(module.base);
+
+        let symbols = module.symbols();
+        let symbol = if symbols.is_empty() {
+            fallback_symbol(addr)
+        } else {
+            let index = symbols.partition_point(|sym| sym.start <= module_offset as u64);
+            index
+                .checked_sub(1)
+                .map(|index| &symbols[index])
+                .filter(|sym| sym.size == 0 || module_offset as u64 - sym.start < sym.size)
+                .map(|sym| SymbolLocation {
+                    name: sym.name.clone(),
+                    offset: module_offset - sym.start as usize,
+                    size: (sym.size != 0).then_some(sym.size),
+                })
+        };
+
+        Some(SymbolizedAddress {
+            module_path: module.path.clone(),
+            module_offset,
+            symbol,
+        })
+    }
+
+    /// Clears the cache, so that modules and their symbols are read again.
+    pub fn clear(&mut self) {
+        self.modules.clear();
+    }
+
+    fn find_module(&self, addr: usize) -> Option<usize> {
+        self.modules.iter().position(|module| module.contains(addr))
+    }
+
+    /// Updates the list of loaded modules, keeping the symbols of modules that are still loaded.
+    fn refresh(&mut self) {
+        let mut old = std::mem::take(&mut self.modules);
+        self.modules = raw::loaded_modules()
+            .map(CachedModule::new)
+            .map(|mut module| {
+                let cached = old
+                    .iter()
+                    .position(|old| old.base == module.base && old.path == module.path);
+                if let Some(cached) = cached {
+                    module.symbols = old.swap_remove(cached).symbols;
+                }
+                module
+            })
+            .collect();
+    }
+}
+
+/// Resolves the symbol containing an address using the dynamic linker.
+fn fallback_symbol(addr: usize) -> Option<SymbolLocation> {
+    let info = unsafe { raw::get_address_info(addr as *const c_void) }?;
+    let name = info.sym_name?;
+    let start = info.sym_addr?.as_ptr() as usize;
+    Some(SymbolLocation {
+        name: name.to_string_lossy().into(),
+        offset: addr - start,
+        size: None,
+    })
+}
+
+impl std::fmt::Debug for Symbolizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Symbolizer")
+            .field("modules", &self.modules.iter().map(|module| &module.path).collect::<Vec<_>>())
+            .finish()
+    }
+}
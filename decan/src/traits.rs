@@ -1,41 +1,182 @@
-use std::{ffi::CStr, mem, ptr::NonNull};
+use std::{ffi::{c_void, CStr}, mem, path::Path, ptr::NonNull};
 
-use crate::{raw, util, SymbolError, SymbolGroupError};
+use crate::{
+    elf::ElfLibrary, raw, util, Data, ElfError, Func, ProbeReport, SymbolError, SymbolGroupErrors,
+    SymbolInfo, VerificationReport,
+};
 
 mod sealed {
     pub trait Sealed {}
 }
 
+/// Whether a [`Symbol`] refers to code or data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    /// The symbol is a function.
+    Function,
+    /// The symbol is a variable or other data object.
+    Data,
+}
+
 /// Trait for types that can be loaded from a dynamic library.
+/// # Safety
+/// Implementors must be pointer-sized types that can be soundly created from
+/// the address returned by the platform's symbol lookup function.
 pub unsafe trait Symbol: sealed::Sealed + Sized + 'static {
+    /// Whether this type refers to a function or to data.
+    const KIND: SymbolKind;
+
+    /// Whether this type can represent a missing symbol (i.e. it is an `Option`).
+    const OPTIONAL: bool = false;
+
     /// Loads a symbol with the given name from the specified library.
     /// # Safety
     /// The caller is responsible for ensuring:
     /// - the resulting pointer does not outlive the library owning it. 
     /// - the pointer's type matches that of the exported library symbol.
-    unsafe fn load_from(lib: raw::Handle, name: &CStr) -> Result<Self, SymbolError>;
+    unsafe fn load_from(lib: raw::Handle, name: &CStr) -> Result<Self, SymbolError> {
+        Self::from_lookup(lookup(lib, name))
+    }
+
+    /// Loads a symbol with the given name and version from the specified library.
+    /// # Safety
+    /// See [`Symbol::load_from`].
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    unsafe fn load_versioned_from(
+        lib: raw::Handle,
+        name: &CStr,
+        version: &CStr,
+    ) -> Result<Self, SymbolError> {
+        Self::from_lookup(lookup_versioned(lib, name, version))
+    }
+
+    /// Loads a symbol like [`Symbol::load_from`], but fails with
+    /// [`SymbolError::WrongProvenance`] if the symbol wasn't defined by `lib` itself
+    /// (e.g. it came from one of `lib`'s dependencies, or an `LD_PRELOAD` library).
+    ///
+    /// This is currently supported on glibc and Windows. Pseudo-handles such as
+    /// [`Library::global_scope`][raw::Library::global_scope] don't refer to a single
+    /// library, so this fails with [`SymbolError::Os`] for them.
+    /// # Safety
+    /// See [`Symbol::load_from`].
+    unsafe fn load_strict_from(lib: raw::Handle, name: &CStr) -> Result<Self, SymbolError> {
+        Self::from_lookup(lookup(lib, name).and_then(|ptr| check_provenance(lib, ptr)))
+    }
+
+    /// Loads a symbol like [`Symbol::load_versioned_from`], checking where it
+    /// was defined like [`Symbol::load_strict_from`].
+    /// # Safety
+    /// See [`Symbol::load_strict_from`].
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    unsafe fn load_versioned_strict_from(
+        lib: raw::Handle,
+        name: &CStr,
+        version: &CStr,
+    ) -> Result<Self, SymbolError> {
+        Self::from_lookup(lookup_versioned(lib, name, version).and_then(|ptr| check_provenance(lib, ptr)))
+    }
+
+    /// Converts a symbol address returned by the dynamic linker to this type.
+    /// # Safety
+    /// The pointer's type must match that of the exported library symbol.
+    unsafe fn from_raw(ptr: *mut c_void) -> Result<Self, SymbolError>;
+
+    /// Converts the result of looking up a symbol to this type.
+    ///
+    /// By default, this propagates lookup errors and calls [`Symbol::from_raw`].
+    /// Optional symbols override this to treat absent symbols as `None`.
+    /// # Safety
+    /// See [`Symbol::from_raw`].
+    unsafe fn from_lookup(result: Result<*mut c_void, SymbolError>) -> Result<Self, SymbolError> {
+        Self::from_raw(result?)
+    }
+
+    /// The form of this symbol which cannot outlive the library it came from.
+    /// 
+    /// Function pointers are branded as [`Func`]s and non-null pointers as [`Data`].
+    /// Raw pointers are left as-is, since dereferencing them is already unsafe.
+    type Branded<'lib>: Copy;
+
+    /// Brands this symbol with the lifetime `'lib`.
+    /// # Safety
+    /// The caller is responsible for ensuring that the library owning this symbol
+    /// stays loaded for `'lib`.
+    unsafe fn brand<'lib>(&self) -> Self::Branded<'lib>;
 }
 
 impl<T: Sized + 'static> sealed::Sealed for *const T {}
 unsafe impl<T: Sized + 'static> Symbol for *const T {
-    unsafe fn load_from(lib: raw::Handle, name: &CStr) -> Result<Self, SymbolError> {
-        raw::platform::get_symbol(lib, name).map_or_else(|err| Err(Into::into(err)), |ptr| Ok(ptr as Self))
+    const KIND: SymbolKind = SymbolKind::Data;
+
+    unsafe fn from_raw(ptr: *mut c_void) -> Result<Self, SymbolError> {
+        Ok(ptr as Self)
+    }
+
+    type Branded<'lib> = Self;
+
+    unsafe fn brand<'lib>(&self) -> Self::Branded<'lib> {
+        *self
     }
 }
 
 impl<T: Sized + 'static> sealed::Sealed for *mut T {}
 unsafe impl<T: Sized + 'static> Symbol for *mut T {
-    unsafe fn load_from(lib: raw::Handle, name: &CStr) -> Result<Self, SymbolError> {
-        raw::platform::get_symbol(lib, name).map_or_else(|err| Err(Into::into(err)), |ptr| Ok(ptr as Self))
+    const KIND: SymbolKind = SymbolKind::Data;
+
+    unsafe fn from_raw(ptr: *mut c_void) -> Result<Self, SymbolError> {
+        Ok(ptr as Self)
+    }
+
+    type Branded<'lib> = Self;
+
+    unsafe fn brand<'lib>(&self) -> Self::Branded<'lib> {
+        *self
     }
 }
 
 impl<T: Sized + 'static> sealed::Sealed for NonNull<T> {}
 unsafe impl<T: Sized + 'static> Symbol for NonNull<T> {
-    unsafe fn load_from(lib: raw::Handle, name: &CStr) -> Result<Self, SymbolError> {
-        raw::platform::get_symbol(lib, name)
-            .map_err(Into::into)
-            .and_then(|ptr| NonNull::new(ptr as *mut T).ok_or(SymbolError::null_value::<Self>()))
+    const KIND: SymbolKind = SymbolKind::Data;
+
+    unsafe fn from_raw(ptr: *mut c_void) -> Result<Self, SymbolError> {
+        NonNull::new(ptr as *mut T).ok_or(SymbolError::null_value::<Self>())
+    }
+
+    type Branded<'lib> = Data<'lib, T>;
+
+    unsafe fn brand<'lib>(&self) -> Self::Branded<'lib> {
+        Data::new(*self)
+    }
+}
+
+unsafe fn lookup(lib: raw::Handle, name: &CStr) -> Result<*mut c_void, SymbolError> {
+    raw::platform::get_symbol(lib, name).map_err(|err| SymbolError::from_lookup(name, err))
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+unsafe fn lookup_versioned(
+    lib: raw::Handle,
+    name: &CStr,
+    version: &CStr,
+) -> Result<*mut c_void, SymbolError> {
+    raw::platform::get_versioned_symbol(lib, name, version)
+        .map_err(|err| SymbolError::from_lookup(name, err))
+}
+
+/// Checks that a resolved symbol belongs to `lib`. Null symbols can't be attributed
+/// to any object, so they are left as-is.
+unsafe fn check_provenance(lib: raw::Handle, ptr: *mut c_void) -> Result<*mut c_void, SymbolError> {
+    if !ptr.is_null() {
+        raw::platform::verify_provenance(lib, ptr)?;
+    }
+    Ok(ptr)
+}
+
+/// Treats absent symbols as `None`, propagating any other error.
+fn absent_as_none<T>(result: Result<Option<T>, SymbolError>) -> Result<Option<T>, SymbolError> {
+    match result {
+        Err(err) if err.is_absent() => Ok(None),
+        result => result,
     }
 }
 
@@ -43,43 +184,71 @@ macro_rules! impl_symbol_fn {
     ($($types:ident),* $(,)?) => {
         impl<R: 'static, $($types: 'static),*>  sealed::Sealed for Option<extern "C" fn($($types),*) -> R>  {}
         unsafe impl<R: 'static, $($types: 'static),*> Symbol for Option<extern "C" fn($($types),*) -> R> {
-            unsafe fn load_from(lib: raw::Handle, name: &CStr) -> Result<Self, SymbolError> {
-                raw::platform::get_symbol(lib, name).map_or_else(
-                    |err| Err(err.into()),
-                    |ptr| Ok(mem::transmute::<_, Self>(ptr)),
-                )
+            const KIND: SymbolKind = SymbolKind::Function;
+            const OPTIONAL: bool = true;
+
+            unsafe fn from_raw(ptr: *mut c_void) -> Result<Self, SymbolError> {
+                Ok(mem::transmute::<*mut c_void, Self>(ptr))
+            }
+
+            unsafe fn from_lookup(result: Result<*mut c_void, SymbolError>) -> Result<Self, SymbolError> {
+                absent_as_none(result.and_then(|ptr| Self::from_raw(ptr)))
+            }
+
+            type Branded<'lib> = Option<Func<'lib, <Self as util::HasNonNull>::NonNull>>;
+
+            unsafe fn brand<'lib>(&self) -> Self::Branded<'lib> {
+                self.map(|func| Func::new(func))
             }
         }
 
         impl<R: 'static, $($types: 'static),*>  sealed::Sealed for extern "C" fn($($types),*) -> R  {}
         unsafe impl<R: 'static, $($types: 'static),*> Symbol for extern "C" fn($($types),*) -> R {
-            unsafe fn load_from(lib: raw::Handle, name: &CStr) -> Result<Self, SymbolError> {
-                raw::platform::get_symbol(lib, name)
-                    .map_err(|err| err.into())
-                    .and_then(|ptr| {
-                        mem::transmute::<_, Option<Self>>(ptr).ok_or(SymbolError::null_value::<Self>())
-                    })
+            const KIND: SymbolKind = SymbolKind::Function;
+
+            unsafe fn from_raw(ptr: *mut c_void) -> Result<Self, SymbolError> {
+                mem::transmute::<*mut c_void, Option<Self>>(ptr).ok_or(SymbolError::null_value::<Self>())
+            }
+
+            type Branded<'lib> = Func<'lib, Self>;
+
+            unsafe fn brand<'lib>(&self) -> Self::Branded<'lib> {
+                Func::new(*self)
             }
         }
 
         impl<R: 'static, $($types: 'static),*>  sealed::Sealed for Option<unsafe extern "C" fn($($types),*) -> R>  {}
         unsafe impl<R: 'static, $($types: 'static),*> Symbol for Option<unsafe extern "C" fn($($types),*) -> R> {
-            unsafe fn load_from(lib: raw::Handle, name: &CStr) -> Result<Self, SymbolError> {
-                raw::platform::get_symbol(lib, name).map_or_else(
-                    |err| Err(err.into()),
-                    |ptr| Ok(mem::transmute::<_, Self>(ptr)),
-                )
+            const KIND: SymbolKind = SymbolKind::Function;
+            const OPTIONAL: bool = true;
+
+            unsafe fn from_raw(ptr: *mut c_void) -> Result<Self, SymbolError> {
+                Ok(mem::transmute::<*mut c_void, Self>(ptr))
+            }
+
+            unsafe fn from_lookup(result: Result<*mut c_void, SymbolError>) -> Result<Self, SymbolError> {
+                absent_as_none(result.and_then(|ptr| Self::from_raw(ptr)))
+            }
+
+            type Branded<'lib> = Option<Func<'lib, <Self as util::HasNonNull>::NonNull>>;
+
+            unsafe fn brand<'lib>(&self) -> Self::Branded<'lib> {
+                self.map(|func| Func::new(func))
             }
         }
 
         impl<R: 'static, $($types: 'static),*>  sealed::Sealed for unsafe extern "C" fn($($types),*) -> R  {}
         unsafe impl<R: 'static, $($types: 'static),*> Symbol for unsafe extern "C" fn($($types),*) -> R {
-            unsafe fn load_from(lib: raw::Handle, name: &CStr) -> Result<Self, SymbolError> {
-                raw::platform::get_symbol(lib, name)
-                    .map_err(|err| err.into())
-                    .and_then(|ptr| {
-                        mem::transmute::<_, Option<Self>>(ptr).ok_or(SymbolError::null_value::<Self>())
-                    })
+            const KIND: SymbolKind = SymbolKind::Function;
+
+            unsafe fn from_raw(ptr: *mut c_void) -> Result<Self, SymbolError> {
+                mem::transmute::<*mut c_void, Option<Self>>(ptr).ok_or(SymbolError::null_value::<Self>())
+            }
+
+            type Branded<'lib> = Func<'lib, Self>;
+
+            unsafe fn brand<'lib>(&self) -> Self::Branded<'lib> {
+                Func::new(*self)
             }
         }
     };
@@ -103,14 +272,100 @@ pub unsafe trait SymbolGroup: Sized + 'static {
     /// # Safety
     /// This function expects the type signatures provided by this `SymbolGroup` to
     /// match those of the exported library symbols they are loading.
-    unsafe fn load(handle: raw::Handle) -> Result<Self, SymbolGroupError>;
+    ///
+    /// Groups derived with `#[symbol_group(strict)]` load like [`SymbolGroup::load_strict`].
+    unsafe fn load(handle: raw::Handle) -> Result<Self, SymbolGroupErrors>;
+
+    /// Loads the symbol group like [`SymbolGroup::load`], but requires every symbol to be
+    /// defined by the library itself. See [`Symbol::load_strict_from`], which also
+    /// describes how pseudo-handles are handled.
+    /// # Safety
+    /// See [`SymbolGroup::load`].
+    unsafe fn load_strict(handle: raw::Handle) -> Result<Self, SymbolGroupErrors>;
+
+    /// Checks which symbols in this group the library provides, without loading the group.
+    /// # Safety
+    /// `handle` must be a valid library handle.
+    unsafe fn probe(handle: raw::Handle) -> ProbeReport;
+
+    /// Describes the symbols in this group, including those in subgroups.
+    fn symbols() -> Vec<SymbolInfo>;
+
+    /// The form of this group whose symbols cannot outlive the library they came from.
+    ///
+    /// The derive generates a struct named after the group with a `Branded` suffix, which
+    /// has the same fields, holding each symbol in its [branded][Symbol::Branded] form and
+    /// each subgroup in its branded form.
+    type Branded<'lib>: Copy;
+
+    /// Brands this group's symbols with the lifetime `'lib`.
+    /// # Safety
+    /// The caller is responsible for ensuring that the library owning this group
+    /// stays loaded for `'lib`.
+    unsafe fn brand<'lib>(&self) -> Self::Branded<'lib>;
+
+    /// Checks this group's symbols against a library's dynamic symbol table.
+    /// See [`SymbolGroup::verify_file`].
+    fn verify(library: &ElfLibrary) -> VerificationReport {
+        VerificationReport::verify(library, Self::symbols())
+    }
+
+    /// Checks whether a library file provides this group's symbols, with matching
+    /// names, versions and types, without loading it.
+    ///
+    /// Since the library is never loaded, none of its code is run. This makes it suitable
+    /// for checking untrusted libraries, or libraries built for another system.
+    fn verify_file<P: AsRef<Path>>(path: P) -> Result<VerificationReport, ElfError> {
+        Ok(Self::verify(&ElfLibrary::open(path)?))
+    }
 }
 
 unsafe impl<G: SymbolGroup> SymbolGroup for Option<G> {
-    /// Loads the symbol group from the library handle. If it fails to load,
-    /// it will simply return `None`.
-    unsafe fn load(handle: raw::Handle) -> Result<Self, SymbolGroupError> {
-        Ok(G::load(handle).ok())
+    /// Loads the symbol group from the library handle. If any of its symbols
+    /// are absent, it will simply return `None`; other errors are propagated.
+    unsafe fn load(handle: raw::Handle) -> Result<Self, SymbolGroupErrors> {
+        absent_group_as_none(G::load(handle))
+    }
+
+    unsafe fn load_strict(handle: raw::Handle) -> Result<Self, SymbolGroupErrors> {
+        absent_group_as_none(G::load_strict(handle))
+    }
+
+    unsafe fn probe(handle: raw::Handle) -> ProbeReport {
+        G::probe(handle)
+    }
+
+    fn symbols() -> Vec<SymbolInfo> {
+        G::symbols().into_iter().map(SymbolInfo::into_optional).collect()
+    }
+
+    type Branded<'lib> = Option<G::Branded<'lib>>;
+
+    unsafe fn brand<'lib>(&self) -> Self::Branded<'lib> {
+        self.as_ref().map(|group| group.brand())
+    }
+}
+
+/// Loads a group with `load`, filling in suggestions for any missing symbols.
+///
+/// This is only done by top-level entry points such as [`Can`][crate::can::Can], not by
+/// [`SymbolGroup::load`] itself, so that optional subgroups, whose errors are discarded,
+/// don't pay for reading the library's export table.
+pub(crate) unsafe fn load_with_suggestions<G>(
+    handle: raw::Handle,
+    load: unsafe fn(raw::Handle) -> Result<G, SymbolGroupErrors>,
+) -> Result<G, SymbolGroupErrors> {
+    load(handle).map_err(|mut errors| {
+        errors.add_suggestions(handle);
+        errors
+    })
+}
+
+fn absent_group_as_none<G>(result: Result<G, SymbolGroupErrors>) -> Result<Option<G>, SymbolGroupErrors> {
+    match result {
+        Ok(group) => Ok(Some(group)),
+        Err(errors) if errors.iter().all(|err| err.inner().is_absent()) => Ok(None),
+        Err(errors) => Err(errors),
     }
 }
 
@@ -121,4 +376,10 @@ pub trait LibraryHandle: Send + Sync {
     /// # Safety
     /// The caller should not free the library while it is in use.
     unsafe fn as_raw(&self) -> raw::Handle;
+}
+
+impl<H: LibraryHandle + ?Sized> LibraryHandle for &H {
+    unsafe fn as_raw(&self) -> raw::Handle {
+        (**self).as_raw()
+    }
 }
\ No newline at end of file
@@ -0,0 +1,203 @@
+use crate::{
+    elf::{ElfLibrary, SymbolType},
+    Symbol, SymbolKind,
+};
+
+/// A description of a single symbol in a [`SymbolGroup`][crate::SymbolGroup].
+///
+/// These are created by [`SymbolGroup::symbols`][crate::SymbolGroup::symbols],
+/// which is usually generated by `#[derive(SymbolGroup)]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolInfo {
+    path: Box<str>,
+    name: &'static str,
+    version: Option<&'static str>,
+    kind: SymbolKind,
+    optional: bool,
+}
+
+impl SymbolInfo {
+    /// Describes a group member of type `T`.
+    pub fn new<T: Symbol, P: Into<Box<str>>>(
+        path: P,
+        name: &'static str,
+        version: Option<&'static str>,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            name,
+            version,
+            kind: T::KIND,
+            optional: T::OPTIONAL,
+        }
+    }
+
+    /// Prefixes the path of a subgroup's member with `prefix`.
+    pub fn nested(mut self, prefix: &str) -> Self {
+        self.path = format!("{prefix}.{}", self.path).into();
+        self
+    }
+
+    pub(crate) fn into_optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
+    /// The path of the group member, e.g. `ext.gl_foo`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The name of the symbol.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The version of the symbol, if a specific version is requested.
+    pub fn version(&self) -> Option<&'static str> {
+        self.version
+    }
+
+    /// Whether the symbol is a function or data.
+    pub fn kind(&self) -> SymbolKind {
+        self.kind
+    }
+
+    /// Returns true if the group can be loaded without this symbol, either because
+    /// the member is an `Option` or because it is part of an optional subgroup.
+    pub fn is_optional(&self) -> bool {
+        self.optional
+    }
+}
+
+/// The result of checking a single symbol against a library file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationStatus {
+    /// The library exports the symbol with a compatible type.
+    Found,
+    /// The library does not export the symbol (or the requested version of it).
+    Missing,
+    /// The library exports the symbol, but its type doesn't match the group member,
+    /// e.g. a function is declared where the library exports a variable.
+    WrongType {
+        /// The type of the exported symbol.
+        found: SymbolType,
+    },
+}
+
+/// A single entry of a [`VerificationReport`].
+#[derive(Debug, Clone)]
+pub struct VerificationEntry {
+    info: SymbolInfo,
+    status: VerificationStatus,
+}
+
+impl VerificationEntry {
+    /// The symbol that was checked.
+    pub fn info(&self) -> &SymbolInfo {
+        &self.info
+    }
+
+    /// The result of checking the symbol.
+    pub fn status(&self) -> &VerificationStatus {
+        &self.status
+    }
+
+    /// Returns true if this symbol would not prevent the group from loading,
+    /// i.e. it was found, or it is optional and missing.
+    pub fn is_ok(&self) -> bool {
+        match self.status {
+            VerificationStatus::Found => true,
+            VerificationStatus::Missing => self.info.optional,
+            VerificationStatus::WrongType { .. } => false,
+        }
+    }
+}
+
+/// A report of whether a library file satisfies a [`SymbolGroup`][crate::SymbolGroup].
+///
+/// This is created by [`SymbolGroup::verify_file`][crate::SymbolGroup::verify_file].
+#[derive(Debug, Clone, Default)]
+pub struct VerificationReport(Vec<VerificationEntry>);
+
+impl VerificationReport {
+    /// Checks a set of symbols against a library's dynamic symbol table.
+    pub fn verify<I: IntoIterator<Item = SymbolInfo>>(library: &ElfLibrary, symbols: I) -> Self {
+        Self(
+            symbols
+                .into_iter()
+                .map(|info| {
+                    let found = match info.version {
+                        Some(version) => library.lookup_versioned(info.name, version),
+                        None => library.lookup(info.name),
+                    };
+                    let status = match found.map(|sym| sym.kind()) {
+                        None => VerificationStatus::Missing,
+                        Some(kind) if kind_matches(info.kind, kind) => VerificationStatus::Found,
+                        Some(found) => VerificationStatus::WrongType { found },
+                    };
+                    VerificationEntry { info, status }
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns true if the library provides every required symbol with a matching type.
+    pub fn is_ok(&self) -> bool {
+        self.0.iter().all(VerificationEntry::is_ok)
+    }
+
+    /// Iterates over the entries for symbols that would prevent the group from loading.
+    pub fn failures(&self) -> impl Iterator<Item = &VerificationEntry> {
+        self.0.iter().filter(|entry| !entry.is_ok())
+    }
+
+    /// Looks up the entry for a group member by its path.
+    pub fn get(&self, path: &str) -> Option<&VerificationEntry> {
+        self.0.iter().find(|entry| entry.info.path() == path)
+    }
+
+    /// Iterates over the entries in the report.
+    pub fn iter(&self) -> std::slice::Iter<'_, VerificationEntry> {
+        self.0.iter()
+    }
+
+    /// Returns the entries in the report as a slice.
+    pub fn entries(&self) -> &[VerificationEntry] {
+        &self.0
+    }
+}
+
+impl IntoIterator for VerificationReport {
+    type Item = VerificationEntry;
+    type IntoIter = std::vec::IntoIter<VerificationEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a VerificationReport {
+    type Item = &'a VerificationEntry;
+    type IntoIter = std::slice::Iter<'a, VerificationEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// Returns true if an exported symbol of type `found` can be loaded as a member of kind `expected`.
+///
+/// Untyped symbols (commonly defined in assembly) are accepted as either.
+fn kind_matches(expected: SymbolKind, found: SymbolType) -> bool {
+    match expected {
+        SymbolKind::Function => matches!(
+            found,
+            SymbolType::Func | SymbolType::GnuIfunc | SymbolType::NoType
+        ),
+        SymbolKind::Data => matches!(
+            found,
+            SymbolType::Object | SymbolType::Tls | SymbolType::Common | SymbolType::NoType
+        ),
+    }
+}
@@ -0,0 +1,286 @@
+use std::ffi::{c_char, c_void};
+
+use decan::{
+    borrow::LibraryBorrowExt, can::Can, raw::Library, ElfError, LoadError, LoadErrorKind, LoadMode,
+    SymbolGroup,
+};
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+const LIBC_NAME: &str = "libc.so.6";
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[test]
+fn test_load_by_name() {
+    let libc = Library::load_by_name(LIBC_NAME).unwrap();
+    let strlen = unsafe {
+        libc.borrow_symbol::<unsafe extern "C" fn(*const c_char) -> usize>(c"strlen")
+            .unwrap()
+    };
+    assert_eq!(unsafe { strlen.call(c"hello".as_ptr()) }, 5);
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[test]
+fn test_soname() {
+    let libc = Library::load_by_name(LIBC_NAME).unwrap();
+    assert_eq!(libc.soname().unwrap().unwrap().to_str().unwrap(), LIBC_NAME);
+    assert!(Library::global_scope().soname().is_err());
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[test]
+fn test_elf_exports() {
+    use decan::elf::{ElfLibrary, SymbolBinding, SymbolType};
+
+    let libc_path = Library::load_by_name(LIBC_NAME).unwrap().path().unwrap();
+    let libc = ElfLibrary::open(libc_path).unwrap();
+
+    assert_eq!(libc.soname(), Some(LIBC_NAME));
+    assert!(libc.has_hash_table());
+    // the oldest symbol version differs between architectures
+    #[cfg(target_arch = "x86_64")]
+    assert!(libc.versions().iter().any(|version| version == "GLIBC_2.2.5"));
+    assert!(libc.versions().iter().any(|version| version.starts_with("GLIBC_2.")));
+
+    let strlen = libc.lookup("strlen").unwrap();
+    assert!(strlen.version().unwrap().starts_with("GLIBC_"));
+    assert_eq!(strlen.section(), Some(".text"));
+    assert!(matches!(strlen.kind(), SymbolType::Func | SymbolType::GnuIfunc));
+    assert!(strlen.size() > 0);
+
+    let stdout = libc.lookup("stdout").unwrap();
+    assert_eq!(stdout.kind(), SymbolType::Object);
+    assert_eq!(stdout.binding(), SymbolBinding::Global);
+    assert!(libc.lookup("decan_does_not_exist").is_none());
+}
+
+#[test]
+fn test_load_by_name_missing() {
+    let err = Library::load_by_name("libdecan_does_not_exist.so").err().unwrap();
+    assert!(matches!(err, LoadError::Loader { mode: LoadMode::Name, .. }));
+    assert_eq!(err.kind(), LoadErrorKind::NotFound);
+    assert_eq!(err.path().unwrap().to_str(), Some("libdecan_does_not_exist.so"));
+}
+
+/// Compiles `source` into `dir/lib{name}.so` with rustc.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn compile_cdylib(dir: &std::path::Path, name: &str, source: &str, args: &[&str]) -> std::path::PathBuf {
+    let source_path = dir.join(format!("{name}.rs"));
+    std::fs::write(&source_path, source).unwrap();
+    let output = dir.join(format!("lib{name}.so"));
+    let status = std::process::Command::new("rustc")
+        .args(["--crate-type", "cdylib", "--edition", "2021", "--crate-name", name])
+        .args(args)
+        .arg("-o")
+        .arg(&output)
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success(), "Failed to compile {name}!");
+    output
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[test]
+fn test_load_dependency_errors() {
+    use decan::raw::{Binding, LoadOptions};
+
+    let dir = std::env::temp_dir().join(format!("decan-dependency-errors-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let dependency = compile_cdylib(
+        &dir,
+        "decan_dependency",
+        "#[no_mangle] pub extern \"C\" fn decan_dependency_value() -> i32 { 1 }",
+        &[],
+    );
+    let dependent = compile_cdylib(
+        &dir,
+        "decan_dependent",
+        "extern \"C\" { fn decan_dependency_value() -> i32; }
+        #[no_mangle] pub extern \"C\" fn decan_dependent_value() -> i32 { unsafe { decan_dependency_value() } }",
+        &["-L", dir.to_str().unwrap(), "-l", "dylib=decan_dependency", "-C", "link-args=-Wl,-rpath,$ORIGIN"],
+    );
+
+    // the dependency no longer exports the symbol the dependent library was linked against
+    compile_cdylib(&dir, "decan_dependency", "#[no_mangle] pub extern \"C\" fn decan_other_value() {}", &[]);
+    let options = LoadOptions::new().binding(Binding::Now);
+    let err = Library::load_with(&dependent, &options).err().unwrap();
+    assert_eq!(err.kind(), LoadErrorKind::UndefinedSymbol { name: "decan_dependency_value".into() });
+
+    std::fs::remove_file(&dependency).unwrap();
+    let err = Library::load_with(&dependent, &options).err().unwrap();
+    assert_eq!(err.kind(), LoadErrorKind::MissingDependency { name: "libdecan_dependency.so".into() });
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[test]
+fn test_load_architecture_mismatch() {
+    let dir = std::env::temp_dir().join(format!("decan-architecture-mismatch-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = compile_cdylib(&dir, "decan_foreign", "#[no_mangle] pub extern \"C\" fn decan_foreign() {}", &[]);
+
+    // rewrite e_machine, so that the library claims to be built for another architecture
+    let mut image = std::fs::read(&path).unwrap();
+    let machine = u16::from_ne_bytes([image[18], image[19]]);
+    let foreign: u16 = if machine == 62 { 183 } else { 62 };
+    image[18..20].copy_from_slice(&foreign.to_ne_bytes());
+    std::fs::write(&path, image).unwrap();
+
+    let err = Library::load(&path).err().unwrap();
+    assert_eq!(err.kind(), LoadErrorKind::ArchitectureMismatch);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_load_invalid_format() {
+    let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+    let err = Library::load(manifest).err().unwrap();
+    assert_eq!(err.kind(), LoadErrorKind::InvalidFormat);
+    assert!(err.message().is_some());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_preflight_invalid_format() {
+    let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+    assert!(matches!(decan::preflight(manifest), Err(ElfError::BadMagic)));
+}
+
+#[derive(SymbolGroup)]
+pub struct LibcStrlen {
+    pub strlen: unsafe extern "C" fn(*const c_char) -> usize,
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[derive(SymbolGroup)]
+#[symbol_group(strict)]
+pub struct LibcStrlenStrict {
+    pub strlen: unsafe extern "C" fn(*const c_char) -> usize,
+}
+
+#[derive(SymbolGroup)]
+pub struct LibcStrlenMissing {
+    pub strlenn: unsafe extern "C" fn(*const c_char) -> usize,
+}
+
+#[test]
+fn test_this_process() {
+    let can = unsafe { Can::<_, LibcStrlen>::with_handle(Library::this_process().unwrap()).unwrap() };
+    assert_eq!(unsafe { can.symbols().strlen.call(c"hello".as_ptr()) }, 5);
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[test]
+fn test_get_if_loaded() {
+    assert!(Library::is_loaded(LIBC_NAME));
+    assert!(!Library::is_loaded("libdecan_does_not_exist.so"));
+    // the failed lookup doesn't leave an error behind for the next caller of dlerror()
+    assert!(unsafe { libc::dlerror() }.is_null());
+    assert!(matches!(Library::get_if_loaded("libc\0.so"), Err(LoadError::CStr(_))));
+
+    let can = unsafe { Can::<_, LibcStrlen>::get_if_loaded(LIBC_NAME).unwrap().unwrap() };
+    assert_eq!(unsafe { can.symbols().strlen.call(c"resident".as_ptr()) }, 8);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_pseudo_handles() {
+    for lib in [Library::global_scope(), Library::next_after_caller()] {
+        assert!(lib.is_pseudo());
+        let strlen = unsafe { lib.borrow_group::<LibcStrlen>().unwrap() };
+        assert_eq!(unsafe { strlen.strlen.call(c"decan".as_ptr()) }, 5);
+        lib.close().unwrap();
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_pseudo_handles_missing_symbol() {
+    // reading the export table of a pseudo-handle for suggestions used to dereference it
+    for lib in [Library::global_scope(), Library::next_after_caller()] {
+        let result = unsafe { Can::<_, LibcStrlenMissing>::with_handle(lib) };
+        let Err(errors) = result else {
+            panic!("expected symbol errors");
+        };
+        assert!(errors.errors()[0].inner().is_absent());
+        assert!(errors.errors()[0].suggestions().is_empty());
+    }
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[test]
+fn test_pseudo_handles_strict() {
+    use decan::SymbolError;
+
+    // provenance can't be checked against a pseudo-handle, which has no link map
+    for lib in [Library::global_scope(), Library::next_after_caller()] {
+        let Err(errors) = (unsafe { Can::<_, LibcStrlenStrict>::with_handle(lib) }) else {
+            panic!("expected symbol errors");
+        };
+        assert!(matches!(errors.errors()[0].inner(), SymbolError::Os(_)));
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_loaded_modules() {
+    let modules: Vec<_> = decan::raw::loaded_modules().collect();
+    assert!(modules[0].is_main_program());
+
+    let libc = modules
+        .iter()
+        .find(|module| module.path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("libc.")))
+        .unwrap();
+    assert!(!libc.program_headers.is_empty());
+
+    let handle = unsafe { libc.open_non_owning().unwrap() };
+    let strlen = unsafe { handle.borrow_group::<LibcStrlen>().unwrap() };
+    assert_eq!(unsafe { strlen.strlen.call(c"modules".as_ptr()) }, 7);
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu", target_arch = "x86_64"))]
+type MemcpyFn = unsafe extern "C" fn(*mut c_void, *const c_void, usize) -> *mut c_void;
+
+#[cfg(all(target_os = "linux", target_env = "gnu", target_arch = "x86_64"))]
+#[derive(SymbolGroup)]
+pub struct LibcMemcpy {
+    #[symbol(name = "memcpy", version = "GLIBC_2.14")]
+    pub memcpy_2_14: MemcpyFn,
+    #[symbol(version = "GLIBC_2.2.5")]
+    pub memcpy: MemcpyFn,
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu", target_arch = "x86_64"))]
+#[derive(SymbolGroup)]
+pub struct LibcMemcpyMissing {
+    #[symbol(version = "GLIBC_0.0")]
+    pub memcpy: MemcpyFn,
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu", target_arch = "x86_64"))]
+#[test]
+fn test_borrow_versioned_symbol() {
+    let libc = Library::load_by_name(LIBC_NAME).unwrap();
+    let memcpy = unsafe {
+        libc.borrow_versioned_symbol::<MemcpyFn>(c"memcpy", c"GLIBC_2.14").unwrap()
+    };
+
+    let src = [1u8, 2, 3, 4];
+    let mut dst = [0u8; 4];
+    unsafe { memcpy.call(dst.as_mut_ptr().cast(), src.as_ptr().cast(), src.len()) };
+    assert_eq!(src, dst);
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu", target_arch = "x86_64"))]
+#[test]
+fn test_versioned_group() {
+    let libc = Library::load_by_name(LIBC_NAME).unwrap();
+    assert!(libc.probe_group::<LibcMemcpy>().all_resolved());
+    assert!(unsafe { libc.borrow_group::<LibcMemcpy>() }.is_ok());
+
+    let errors = unsafe { libc.borrow_group::<LibcMemcpyMissing>() }.err().unwrap();
+    assert_eq!(errors.errors()[0].name(), "memcpy@GLIBC_0.0");
+}
@@ -1,6 +1,12 @@
-use std::{path::{Path, PathBuf}, process::Command};
+use std::{path::{Path, PathBuf}, process::Command, ptr::NonNull};
 
-use decan::{can::Can, SymbolGroup};
+use decan::{
+    borrow::LibraryBorrowExt,
+    can::Can,
+    elf::{ElfLibrary, SymbolType},
+    raw::{Library, LoadOptions},
+    ElfError, LibraryHandle, LoadOrSymbolGroupError, SymbolError, SymbolGroup, VerificationStatus,
+};
 
 #[derive(SymbolGroup)]
 pub struct DecanTestlib {
@@ -8,22 +14,190 @@ pub struct DecanTestlib {
     pub square_int: Option<extern "C" fn(i32) -> i32>,
 }
 
+#[derive(SymbolGroup)]
+#[symbol_group(accessors)]
+pub struct DecanTestlibAccessors {
+    print_message: extern "C" fn(),
+    #[symbol = "square_int"]
+    square: Option<extern "C" fn(i32) -> i32>,
+}
+
+#[derive(SymbolGroup)]
+pub struct DecanTestlibMissing {
+    pub square_int: extern "C" fn(i32) -> i32,
+    pub cube_int: extern "C" fn(i32) -> i32,
+    #[subgroup]
+    pub ext: DecanTestlibMissingExt,
+}
+
+#[derive(SymbolGroup)]
+pub struct DecanTestlibMissingExt {
+    #[symbol = "decan_ext_foo"]
+    pub foo: extern "C" fn(),
+}
+
+#[derive(SymbolGroup)]
+pub struct DecanTestlibOptional {
+    pub square_int: Option<extern "C" fn(i32) -> i32>,
+    pub cube_int: Option<extern "C" fn(i32) -> i32>,
+    #[subgroup]
+    pub ext: Option<DecanTestlibMissingExt>,
+}
+
 #[test]
 fn test_load() {
     let testlib_path = compile_testlib();
 
     let can = unsafe { Can::<_, DecanTestlib>::load(testlib_path).unwrap() };
 
-    (can.print_message)();
-    assert_eq!((can.square_int.unwrap())(2), 4);
+    can.symbols().print_message.call();
+    assert_eq!(can.symbols().square_int.unwrap().call(2), 4);
+}
+
+#[test]
+fn test_accessors() {
+    let testlib_path = compile_testlib();
+
+    let can = unsafe { Can::<_, DecanTestlibAccessors>::load(testlib_path).unwrap() };
+
+    let symbols = can.symbols();
+    symbols.print_message().call();
+    assert_eq!(symbols.square().unwrap().call(5), 25);
+}
+
+#[test]
+fn test_missing_symbols() {
+    let testlib_path = compile_testlib();
+
+    let result = unsafe { Can::<_, DecanTestlibMissing>::load(testlib_path) };
+    let Err(LoadOrSymbolGroupError::Symbol(errors)) = result else {
+        panic!("expected symbol errors");
+    };
+
+    let reported: Vec<(&str, &str)> = errors.iter().map(|err| (err.path(), err.name())).collect();
+    assert_eq!(reported, [("cube_int", "cube_int"), ("ext.foo", "decan_ext_foo")]);
+    assert!(errors.iter().all(|err| matches!(err.inner(), SymbolError::NotFound { .. })));
+}
+
+#[derive(SymbolGroup)]
+pub struct DecanTestlibTypo {
+    #[symbol = "squareInt"]
+    pub square: extern "C" fn(i32) -> i32,
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[test]
+fn test_missing_symbol_suggestions() {
+    let testlib_path = compile_testlib();
+
+    let result = unsafe { Can::<_, DecanTestlibTypo>::load(testlib_path) };
+    let Err(LoadOrSymbolGroupError::Symbol(errors)) = result else {
+        panic!("expected symbol errors");
+    };
+
+    let error = &errors.errors()[0];
+    assert_eq!(error.suggestions(), ["square_int"]);
+    assert!(error.to_string().ends_with("(did you mean `square_int`?)"));
+
+    // loading the group directly, as a parent group does with its subgroups, skips suggestions
+    let lib = Library::load(compile_testlib()).unwrap();
+    let Err(mut errors) = (unsafe { DecanTestlibTypo::load(lib.as_raw()) }) else {
+        panic!("expected symbol errors");
+    };
+    assert!(errors.errors()[0].suggestions().is_empty());
+    unsafe { errors.add_suggestions(lib.as_raw()) };
+    assert_eq!(errors.errors()[0].suggestions(), ["square_int"]);
+}
+
+#[test]
+fn test_optional_symbols() {
+    let testlib_path = compile_testlib();
+
+    let can = unsafe { Can::<_, DecanTestlibOptional>::load(testlib_path).unwrap() };
+    assert_eq!(can.symbols().square_int.unwrap().call(3), 9);
+    assert!(can.symbols().cube_int.is_none());
+    assert!(can.symbols().ext.is_none());
+}
+
+#[test]
+fn test_probe() {
+    let testlib_path = compile_testlib();
+
+    let lib = Library::load(testlib_path).unwrap();
+    let report = lib.probe_group::<DecanTestlibMissing>();
+
+    assert!(!report.all_resolved());
+    assert!(report.get("square_int").unwrap().is_resolved());
+    assert!(report.get("cube_int").unwrap().error().is_some());
+    assert!(!report.get("ext.foo").unwrap().is_resolved());
+}
+
+#[test]
+fn test_close() {
+    let testlib_path = compile_testlib();
+
+    let can = unsafe { Can::<_, DecanTestlib>::load(testlib_path).unwrap() };
+    can.close().unwrap();
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[test]
+fn test_link_map_info() {
+    let testlib_path = compile_testlib();
+
+    let lib = Library::load(&testlib_path).unwrap();
+    assert_eq!(lib.path().unwrap(), testlib_path.canonicalize().unwrap());
+    assert!(!lib.base_address().unwrap().is_null());
+    assert!(lib.soname().unwrap().is_none());
+    assert!(lib.dependencies().unwrap().iter().any(|dep| dep.to_bytes().starts_with(b"libc.so")));
+}
+
+#[derive(SymbolGroup)]
+pub struct DecanTestlibCounter {
+    pub increment_counter: extern "C" fn() -> i32,
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[test]
+fn test_namespaces() {
+    use decan::raw::Namespace;
+
+    let testlib_path = compile_testlib();
+
+    let ns1 = Namespace::new();
+    let ns2 = Namespace::new();
+    let can1 = unsafe { Can::<_, DecanTestlibCounter>::load_in(&ns1, &testlib_path).unwrap() };
+    let can2 = unsafe { Can::<_, DecanTestlibCounter>::load_in(&ns2, &testlib_path).unwrap() };
+    assert_ne!(ns1.id(), ns2.id());
+
+    assert_eq!(can1.symbols().increment_counter.call(), 1);
+    assert_eq!(can1.symbols().increment_counter.call(), 2);
+    assert_eq!(can2.symbols().increment_counter.call(), 1);
+
+    // loading into an existing namespace reuses the copy that's already there
+    let can1_again = unsafe { Can::<_, DecanTestlibCounter>::load_in(&ns1, &testlib_path).unwrap() };
+    assert_eq!(can1_again.symbols().increment_counter.call(), 3);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_load_with() {
+    use decan::raw::{Binding, Visibility};
+
+    let testlib_path = compile_testlib();
+
+    let options = LoadOptions::new().binding(Binding::Now).visibility(Visibility::Local);
+    let can = unsafe { Can::<_, DecanTestlib>::load_with(testlib_path, &options).unwrap() };
+
+    assert_eq!(can.symbols().square_int.unwrap().call(3), 9);
 }
 
 fn compute_dll_name(name: &str) -> String {
-    #[cfg(any(target_os = "windows"))]
+    #[cfg(target_os = "windows")]
     return format!("{name}.dll");
-    #[cfg(any(target_os = "macos"))]
+    #[cfg(target_os = "macos")]
     return format!("lib{name}.dylib");
-    #[cfg(any(target_os = "linux"))]
+    #[cfg(target_os = "linux")]
     return format!("lib{name}.so");
 
 }
@@ -41,3 +215,381 @@ fn compile_testlib() -> PathBuf {
 
     project_root.join("target/debug").join(compute_dll_name("decan_testlib"))
 }
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_preflight() {
+    let testlib_path = compile_testlib();
+
+    let report = decan::preflight(&testlib_path).unwrap();
+    assert!(report.is_ok(), "{:?}", report.issues());
+    assert!(report.dependencies().iter().any(|dep| dep.ends_with("libc.so.6")));
+
+    let lib = ElfLibrary::open(&testlib_path).unwrap();
+    assert!(lib.exports().any(|sym| sym.name() == "square_int"));
+    assert!(lib.needed().iter().any(|dep| dep.starts_with("libc.so")));
+}
+
+#[cfg(all(target_os = "linux", target_pointer_width = "64", target_endian = "little"))]
+#[test]
+fn test_elf_untrusted_offsets() {
+    let data = std::fs::read(compile_testlib()).unwrap();
+    let field = |data: &[u8], offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+
+    // a program header table that would wrap around the address space
+    let mut corrupt = data.clone();
+    corrupt[32..40].copy_from_slice(&(u64::MAX - 8).to_le_bytes());
+    assert!(matches!(ElfLibrary::parse(&corrupt), Err(ElfError::Truncated)));
+
+    // a loadable segment whose end would overflow
+    let phoff = field(&data, 32) as usize;
+    let phentsize = u16::from_le_bytes([data[54], data[55]]) as usize;
+    let load = (0..u16::from_le_bytes([data[56], data[57]]) as usize)
+        .map(|i| phoff + i * phentsize)
+        .find(|&base| data[base..base + 4] == 1u32.to_le_bytes())
+        .unwrap();
+    let mut corrupt = data.clone();
+    corrupt[load + 16..load + 24].copy_from_slice(&u64::MAX.to_le_bytes());
+    corrupt[load + 32..load + 40].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(ElfLibrary::parse(&corrupt).is_err());
+}
+
+#[derive(SymbolGroup)]
+#[symbol_group(strict)]
+pub struct DecanTestlibStrict {
+    pub square_int: extern "C" fn(i32) -> i32,
+    // provided by libc, which decan-testlib depends on
+    pub strlen: unsafe extern "C" fn(*const std::ffi::c_char) -> usize,
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[test]
+fn test_strict_provenance() {
+    let testlib_path = compile_testlib();
+
+    let lib = Library::load(&testlib_path).unwrap();
+    assert!(unsafe { lib.borrow_group_strict::<DecanTestlib>() }.is_ok());
+    assert!(unsafe { lib.borrow_symbol::<unsafe extern "C" fn(*const std::ffi::c_char) -> usize>(c"strlen") }.is_ok());
+
+    let err = unsafe { lib.borrow_symbol_strict::<unsafe extern "C" fn(*const std::ffi::c_char) -> usize>(c"strlen") }
+        .err()
+        .unwrap();
+    let SymbolError::WrongProvenance { expected, actual } = err else {
+        panic!("expected provenance error, got {err}");
+    };
+    assert_eq!(expected, testlib_path.canonicalize().unwrap());
+    assert!(actual.to_string_lossy().contains("libc.so"));
+
+    let Err(errors) = (unsafe { Can::<_, DecanTestlibStrict>::with_handle(lib) }) else {
+        panic!("expected symbol errors");
+    };
+    let reported: Vec<&str> = errors.iter().map(|err| err.path()).collect();
+    assert_eq!(reported, ["strlen"]);
+}
+
+#[derive(SymbolGroup)]
+pub struct DecanTestlibWrongType {
+    #[symbol = "square_int"]
+    pub square: NonNull<i32>,
+}
+
+#[test]
+fn test_verify_file() {
+    let testlib_path = compile_testlib();
+
+    assert!(DecanTestlib::verify_file(&testlib_path).unwrap().is_ok());
+    assert!(DecanTestlibOptional::verify_file(&testlib_path).unwrap().is_ok());
+
+    let report = DecanTestlibMissing::verify_file(&testlib_path).unwrap();
+    let failures: Vec<&str> = report.failures().map(|entry| entry.info().path()).collect();
+    assert_eq!(failures, ["cube_int", "ext.foo"]);
+
+    let report = DecanTestlibWrongType::verify_file(&testlib_path).unwrap();
+    assert!(matches!(
+        report.get("square").unwrap().status(),
+        VerificationStatus::WrongType { found: SymbolType::Func }
+    ));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_symbolizer() {
+    let testlib_path = compile_testlib();
+
+    let lib = Library::load(&testlib_path).unwrap();
+    let square = unsafe { lib.borrow_symbol::<extern "C" fn(i32) -> i32>(c"square_int").unwrap() };
+    let addr = unsafe { square.into_raw() } as *const std::ffi::c_void;
+
+    let mut symbolizer = decan::Symbolizer::new();
+    let resolved = symbolizer.symbolize(addr.wrapping_add(1)).unwrap();
+    assert_eq!(resolved.module_path(), testlib_path.canonicalize().unwrap());
+
+    let symbol = resolved.symbol().unwrap();
+    assert_eq!(symbol.name(), "square_int");
+    assert_eq!(symbol.offset(), 1);
+    assert!(symbol.size().unwrap() > 1);
+
+    let base = lib.base_address().unwrap() as usize;
+    assert_eq!(resolved.module_offset(), addr as usize + 1 - base);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_load_from_bytes() {
+    use decan::{LoadError, LoadErrorKind, LoadMode};
+
+    let image = std::fs::read(compile_testlib()).unwrap();
+
+    let can1 = unsafe { Can::<_, DecanTestlibCounter>::load_from_bytes(&image).unwrap() };
+    let can2 = unsafe { Can::<_, DecanTestlibCounter>::load_from_bytes(&image).unwrap() };
+    assert_eq!(can1.symbols().increment_counter.call(), 1);
+    assert_eq!(can2.symbols().increment_counter.call(), 1);
+
+    let square = unsafe { can1.borrow_symbol::<extern "C" fn(i32) -> i32>(c"square_int").unwrap() };
+    assert_eq!(square.call(5), 25);
+
+    let Err(err) = Library::load_from_bytes(b"not a library") else {
+        panic!("loaded an invalid image");
+    };
+    assert!(matches!(err, LoadError::Loader { mode: LoadMode::Memory, .. }));
+    assert_eq!(err.kind(), LoadErrorKind::InvalidFormat);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_lazy_library() {
+    use decan::{include_library, LazyLibrary, LoadErrorKind};
+
+    let image = std::fs::read(compile_testlib()).unwrap();
+    let lazy = LazyLibrary::new(Box::leak(image.into_boxed_slice()));
+    assert!(!lazy.is_loaded());
+
+    let can = unsafe { Can::<_, DecanTestlibCounter>::with_handle(lazy.get().unwrap()).unwrap() };
+    assert!(lazy.is_loaded());
+    assert_eq!(can.symbols().increment_counter.call(), 1);
+    // later uses share the copy loaded the first time
+    let can = unsafe { Can::<_, DecanTestlibCounter>::with_handle(lazy.get().unwrap()).unwrap() };
+    assert_eq!(can.symbols().increment_counter.call(), 2);
+
+    static NOT_A_LIBRARY: LazyLibrary = include_library!("symbol_group.rs");
+    let Err(err) = NOT_A_LIBRARY.get() else {
+        panic!("loaded an invalid image");
+    };
+    assert_eq!(err.kind(), LoadErrorKind::InvalidFormat);
+    assert!(NOT_A_LIBRARY.get_if_loaded().is_none());
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[test]
+fn test_drop_policy() {
+    use std::{mem::ManuallyDrop, panic::catch_unwind};
+
+    use decan::raw::DropPolicy;
+
+    let dir = std::env::temp_dir().join(format!("decan-drop-policy-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // leaked libraries are never closed
+    let leaked = dir.join("libleaked.so");
+    std::fs::copy(compile_testlib(), &leaked).unwrap();
+    drop(Library::load(&leaked).unwrap().with_drop_policy(DropPolicy::Leak));
+    assert!(Library::is_loaded(&leaked));
+
+    // a copy that another library depends on stays mapped after its last close, so closing it
+    // again fails with an error from the dynamic linker instead of touching freed memory
+    let testlib = dir.join("libdecan_drop_testlib.so");
+    std::fs::copy(compile_testlib(), &testlib).unwrap();
+    let source = dir.join("decan_drop_dependent.rs");
+    std::fs::write(
+        &source,
+        "extern \"C\" { fn square_int(x: i32) -> i32; }
+        #[no_mangle] pub extern \"C\" fn square_twice(x: i32) -> i32 { unsafe { square_int(square_int(x)) } }",
+    )
+    .unwrap();
+    let dependent = dir.join("libdecan_drop_dependent.so");
+    let status = Command::new("rustc")
+        .args(["--crate-type", "cdylib", "--edition", "2021", "-L", dir.to_str().unwrap()])
+        .args(["-l", "dylib=decan_drop_testlib", "-C", "link-args=-Wl,-rpath,$ORIGIN", "-o"])
+        .arg(&dependent)
+        .arg(&source)
+        .status()
+        .unwrap();
+    assert!(status.success(), "Failed to compile decan_drop_dependent!");
+
+    let dependent = Library::load(&dependent).unwrap();
+    let library = Library::load(&testlib).unwrap();
+    let handle = unsafe { library.as_raw() };
+    library.close().unwrap();
+    let closed = || ManuallyDrop::into_inner(unsafe { Library::wrap_raw(handle) });
+
+    assert!(closed().close().is_err());
+    drop(closed().with_drop_policy(DropPolicy::Log));
+    drop(closed().with_drop_policy(DropPolicy::Leak));
+    assert!(catch_unwind(|| drop(closed())).is_err());
+
+    // while already panicking, the error is only logged, since a second panic would abort
+    let library = closed();
+    let payload = catch_unwind(move || {
+        let _library = library;
+        panic!("unwinding");
+    })
+    .unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"unwinding"));
+
+    dependent.close().unwrap();
+    assert!(!Library::is_loaded(&testlib));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_reload() {
+    use decan::ReloadError;
+
+    // reload from a private copy, since other tests load the original concurrently
+    let dir = std::env::temp_dir().join(format!("decan-reload-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("libdecan_testlib.so");
+    std::fs::copy(compile_testlib(), &path).unwrap();
+
+    let mut can = unsafe { Can::<_, DecanTestlibCounter>::load(&path).unwrap() };
+    assert_eq!(can.symbols().increment_counter.call(), 1);
+    assert_eq!(can.symbols().increment_counter.call(), 2);
+    let old_function = unsafe { can.symbols().increment_counter.into_raw() } as usize;
+
+    unsafe { can.reload().unwrap() };
+    assert_ne!(unsafe { can.symbols().increment_counter.into_raw() } as usize, old_function);
+    // the counter was handed over through save_state/restore_state
+    assert_eq!(can.symbols().increment_counter.call(), 3);
+    unsafe { can.reload().unwrap() };
+    assert_eq!(can.symbols().increment_counter.call(), 4);
+
+    // nothing is written next to the library
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    drop(can);
+
+    // the path is resolved when the can is loaded, so repointing a symlink doesn't affect reloads
+    let link = dir.join("current.so");
+    std::os::unix::fs::symlink(&path, &link).unwrap();
+    let mut can = unsafe { Can::<_, DecanTestlibCounter>::load(&link).unwrap() };
+    std::fs::remove_file(&link).unwrap();
+    std::os::unix::fs::symlink(dir.join("missing.so"), &link).unwrap();
+    unsafe { can.reload().unwrap() };
+    drop(can);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let lib = Library::load(compile_testlib()).unwrap();
+    let mut can = unsafe { Can::<_, DecanTestlibCounter>::with_handle(lib).unwrap() };
+    assert!(matches!(unsafe { can.reload() }, Err(ReloadError::NoSource)));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_plugin_directory() {
+    use std::time::{Duration, Instant};
+
+    use decan::{PluginDirectory, PluginEvent};
+
+    /// Waits until `count` events have been reported.
+    fn wait_for(plugins: &mut PluginDirectory<DecanTestlibCounter>, count: usize) -> Vec<PluginEvent> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut events = Vec::new();
+        while events.len() < count && Instant::now() < deadline {
+            events.extend(plugins.wait(Some(Duration::from_millis(100))).unwrap());
+        }
+        events
+    }
+
+    let dir = std::env::temp_dir().join(format!("decan-plugins-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let existing = dir.join("libexisting.so");
+    std::fs::copy(compile_testlib(), &existing).unwrap();
+    std::fs::write(dir.join("notes.txt"), "not a plugin").unwrap();
+
+    let mut plugins = unsafe { PluginDirectory::<DecanTestlibCounter>::open(&dir).unwrap() };
+    assert!(matches!(&plugins.poll().unwrap()[..], [PluginEvent::Added(path)] if *path == existing));
+
+    let added = dir.join("libadded.so");
+    std::fs::copy(compile_testlib(), &added).unwrap();
+    std::fs::write(dir.join("libbroken.so"), "not a library").unwrap();
+    let events = wait_for(&mut plugins, 2);
+    assert!(events.iter().any(|event| matches!(event, PluginEvent::Added(path) if *path == added)));
+    assert!(events.iter().any(|event| matches!(event, PluginEvent::Failed { path, .. } if path.ends_with("libbroken.so"))));
+
+    // each plugin is a separate copy of the library
+    let plugin = plugins.get("libadded.so").unwrap().clone();
+    assert_eq!(plugin.symbols().increment_counter.call(), 1);
+    assert_eq!(plugin.symbols().increment_counter.call(), 2);
+    assert_eq!(plugins.get(&existing).unwrap().symbols().increment_counter.call(), 1);
+
+    // removed plugins stay loaded until they are no longer held
+    std::fs::remove_file(&added).unwrap();
+    assert!(matches!(&wait_for(&mut plugins, 1)[..], [PluginEvent::Removed(path)] if *path == added));
+    assert!(plugins.get(&added).is_none());
+    assert_eq!(plugins.retired_count(), 1);
+    assert_eq!(plugin.symbols().increment_counter.call(), 3);
+    drop(plugin);
+    assert!(matches!(&plugins.poll().unwrap()[..], [PluginEvent::Unloaded(path)] if *path == added));
+    assert_eq!(plugins.retired_count(), 0);
+
+    std::fs::copy(compile_testlib(), &existing).unwrap();
+    // nothing holds the previous version, so it is unloaded right away
+    assert!(matches!(
+        &wait_for(&mut plugins, 2)[..],
+        [PluginEvent::Modified(modified), PluginEvent::Unloaded(unloaded)] if *modified == existing && *unloaded == existing
+    ));
+    assert_eq!(plugins.get(&existing).unwrap().symbols().increment_counter.call(), 1);
+
+    // plugins are loaded from memory, so nothing was written to the directory
+    let mut names: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    names.sort();
+    assert_eq!(names, ["libbroken.so", "libexisting.so", "notes.txt"]);
+
+    drop(plugins);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_swap_can() {
+    use decan::SwapCan;
+
+    let dir = std::env::temp_dir().join(format!("decan-swap-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("libdecan_testlib.so");
+    std::fs::copy(compile_testlib(), &path).unwrap();
+
+    let swap = SwapCan::new(unsafe { Can::<_, DecanTestlibCounter>::load(&path).unwrap() });
+    let guard = swap.load();
+    assert_eq!(guard.symbols().increment_counter.call(), 1);
+
+    unsafe { swap.reload().unwrap() };
+    assert_eq!(swap.load().symbols().increment_counter.call(), 2);
+    // the previous version stays loaded while a guard to it exists
+    assert_eq!(swap.retired_count(), 1);
+    assert_eq!(guard.symbols().increment_counter.call(), 2);
+    drop(guard);
+    swap.collect();
+    assert_eq!(swap.retired_count(), 0);
+
+    // readers keep calling into the library while it is reloaded; calling into an
+    // unloaded library would crash. Increments racing with a reload may be lost.
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..2000 {
+                    assert!(swap.load().symbols().increment_counter.call() > 0);
+                }
+            });
+        }
+        for _ in 0..10 {
+            unsafe { swap.reload().unwrap() };
+        }
+    });
+    swap.collect();
+    assert_eq!(swap.retired_count(), 0);
+    assert!(swap.load().symbols().increment_counter.call() > 2);
+
+    drop(swap);
+    std::fs::remove_dir_all(&dir).unwrap();
+}